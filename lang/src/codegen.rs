@@ -1,12 +1,91 @@
 use crate::parse::Node;
 use crate::parse::Node::*;
+use crate::types::Type::TY_BOOL;
+use crate::types::Type;
 
-fn gen_lval(node: Node, stack_size: u32) {
+const ARGREG8: [&str; 6] = ["dil", "sil", "dl", "cl", "r8b", "r9b"];
+const ARGREG16: [&str; 6] = ["di", "si", "dx", "cx", "r8w", "r9w"];
+const ARGREG32: [&str; 6] = ["edi", "esi", "edx", "ecx", "r8d", "r9d"];
+const ARGREG64: [&str; 6] = ["rdi", "rsi", "rdx", "rcx", "r8", "r9"];
+
+// スタックトップのアドレスから値を読み込む
+// 8バイトより小さい型は型の符号に合わせて拡張する
+fn load(ty: &Type) {
+    // 配列と構造体と関数は先頭のアドレスをそのまま値として扱う
+    if ty.is_aggregate() || ty.is_func() {
+        return;
+    }
+    println!("  pop rax");
+    match (ty.size(), ty.is_unsigned()) {
+        (1, false) => println!("  movsx rax, byte ptr [rax]"),
+        (1, true) => println!("  movzx rax, byte ptr [rax]"),
+        (2, false) => println!("  movsx rax, word ptr [rax]"),
+        (2, true) => println!("  movzx rax, word ptr [rax]"),
+        (4, false) => println!("  movsxd rax, dword ptr [rax]"),
+        (4, true) => println!("  mov eax, dword ptr [rax]"),
+        _ => println!("  mov rax, [rax]"),
+    }
+    println!("  push rax");
+}
+
+// rdiの値をraxのアドレスに型のサイズだけ書き込む
+// 構造体はrdiのアドレスから1バイトずつコピーする
+fn store(ty: &Type) {
+    if ty.is_struct() {
+        for i in 0..ty.size() {
+            println!("  mov r8b, [rdi+{}]", i);
+            println!("  mov [rax+{}], r8b", i);
+        }
+        return;
+    }
+    match ty.size() {
+        1 => println!("  mov [rax], dil"),
+        2 => println!("  mov [rax], di"),
+        4 => println!("  mov [rax], edi"),
+        _ => println!("  mov [rax], rdi"),
+    }
+}
+
+// raxの値を型のサイズに切り詰め、型の符号に合わせて拡張し直す
+fn truncate(ty: &Type) {
+    match (ty.size(), ty.is_unsigned()) {
+        (1, false) => println!("  movsx rax, al"),
+        (1, true) => println!("  movzx rax, al"),
+        (2, false) => println!("  movsx rax, ax"),
+        (2, true) => println!("  movzx rax, ax"),
+        (4, false) => println!("  movsxd rax, eax"),
+        (4, true) => println!("  mov eax, eax"),
+        _ => {}
+    }
+}
+
+fn gen_lval(node: Node, scope_count: &mut u32, fname: &String) {
     match node {
-        ND_LVAR { offset } => {
-            println!("  lea rax, [rbp-{}]", stack_size - offset);
+        ND_LVAR { offset, align, .. } => {
+            println!("  lea rax, [rbp-{}]", offset);
+            if align > 16 {
+                println!("  add rax, {}", align - 1);
+                println!("  and rax, -{}", align);
+            }
             println!("  push rax");
         }
+        ND_DEREF { expr } => {
+            gen(*expr, scope_count, fname);
+        }
+        ND_FUNC { name, .. } | ND_GVAR { name, .. } => {
+            println!("  lea rax, {}[rip]", name);
+            println!("  push rax");
+        }
+        ND_MEMBER { expr, offset, .. } => {
+            gen_lval(*expr, scope_count, fname);
+            println!("  pop rax");
+            println!("  add rax, {}", offset);
+            println!("  push rax");
+        }
+        // 構造体の値を計算すると、そのアドレスがスタックに積まれる
+        _ if node.ty().is_struct() => {
+            gen(node, scope_count, fname);
+        }
         _ => {
             eprintln!("代入の左辺値が変数ではありません");
             std::process::exit(1);
@@ -14,9 +93,9 @@ fn gen_lval(node: Node, stack_size: u32) {
     }
 }
 
-fn gen_bin_op(lhs: Node, rhs: Node, scope_count: &mut u32, fname: &String, stack_size: u32) {
-    gen(lhs, scope_count, fname, stack_size);
-    gen(rhs, scope_count, fname, stack_size);
+fn gen_bin_op(lhs: Node, rhs: Node, scope_count: &mut u32, fname: &String) {
+    gen(lhs, scope_count, fname);
+    gen(rhs, scope_count, fname);
 
     println!("  pop rdi");
     println!("  pop rax");
@@ -24,7 +103,6 @@ fn gen_bin_op(lhs: Node, rhs: Node, scope_count: &mut u32, fname: &String, stack
 
 pub fn codegen(funcs: Vec<Node>) {
     let mut scope_count = 0;
-    let argreg = vec!["rdi", "rsi", "rdx", "rcx", "r8", "r9"];
     for fun in funcs {
         match fun {
             ND_FUNCTION {
@@ -32,8 +110,12 @@ pub fn codegen(funcs: Vec<Node>) {
                 body,
                 parameters,
                 stack_size,
+                is_static,
             } => {
-                println!(".global {}", name);
+                println!(".text");
+                if !is_static {
+                    println!(".global {}", name);
+                }
                 println!("{}:", name);
 
                 // Prologue
@@ -44,8 +126,14 @@ pub fn codegen(funcs: Vec<Node>) {
                 let mut i = 0;
                 for param in parameters {
                     match param {
-                        ND_LVAR { offset } => {
-                            println!("  mov [rbp-{}], {}", stack_size - offset, argreg[i]);
+                        ND_LVAR { offset, ty, .. } => {
+                            let reg = match ty.size() {
+                                1 => ARGREG8[i],
+                                2 => ARGREG16[i],
+                                4 => ARGREG32[i],
+                                _ => ARGREG64[i],
+                            };
+                            println!("  mov [rbp-{}], {}", offset, reg);
                             i += 1;
                         }
                         _ => unreachable!(),
@@ -54,7 +142,7 @@ pub fn codegen(funcs: Vec<Node>) {
 
                 // Emit code
                 for bnode in body {
-                    gen(bnode, &mut scope_count, &name, stack_size);
+                    gen(bnode, &mut scope_count, &name);
                 }
                 // Epilogue
                 println!(".L.return.{}:", name);
@@ -62,37 +150,90 @@ pub fn codegen(funcs: Vec<Node>) {
                 println!("  pop rbp");
                 println!("  ret");
             }
+            ND_GVAR_DEF {
+                name,
+                ty,
+                align,
+                init,
+                is_static,
+            } => {
+                // 初期値のない変数は .bss に置いて、実行時に0で埋めてもらう
+                if init.is_some() {
+                    println!(".data");
+                } else {
+                    println!(".bss");
+                }
+                if !is_static {
+                    println!(".global {}", name);
+                }
+                println!(".align {}", align);
+                println!("{}:", name);
+                match init {
+                    Some(data) => {
+                        let mut i = 0;
+                        while i < data.bytes.len() {
+                            match data.relocs.iter().find(|reloc| reloc.offset == i) {
+                                Some(reloc) => {
+                                    println!("  .quad {}{:+}", reloc.label, reloc.addend);
+                                    i += 8;
+                                }
+                                None => {
+                                    println!("  .byte {}", data.bytes[i]);
+                                    i += 1;
+                                }
+                            }
+                        }
+                    }
+                    None => println!("  .zero {}", ty.size()),
+                }
+            }
             _ => unreachable!(),
         }
     }
 }
 
-fn gen(node: Node, scope_count: &mut u32, fname: &String, stack_size: u32) {
-    let argreg = vec!["rdi", "rsi", "rdx", "rcx", "r8", "r9"];
+fn gen(node: Node, scope_count: &mut u32, fname: &String) {
     match node {
         ND_FUNCTION {
             name: _,
             body: _,
             parameters: _,
             stack_size: _,
-        } => unreachable!(),
+            is_static: _,
+        }
+        | ND_GVAR_DEF { .. } => unreachable!(),
         ND_NOTHING => {}
         ND_RETURN { ret } => {
-            gen(*ret, scope_count, fname, stack_size);
+            gen(*ret, scope_count, fname);
             println!("  pop rax");
             println!("  jmp .L.return.{}", fname);
         }
-        ND_FUNCALL { name, args } => {
+        ND_FUNCALL { func, args, ty } => {
+            // 名前で直接呼べない場合は、関数のアドレスを先に積んでおく
+            let direct = match *func {
+                ND_FUNC { name, .. } => Some(name),
+                func => {
+                    gen(func, scope_count, fname);
+                    None
+                }
+            };
             let mut nargs = 0;
             for arg in args {
-                gen(arg, scope_count, fname, stack_size);
+                gen(arg, scope_count, fname);
                 nargs += 1;
             }
             for i in (0..nargs).rev() {
-                println!("  pop {}", argreg[i]);
+                println!("  pop {}", ARGREG64[i]);
             }
+            let target = match direct {
+                Some(name) => name,
+                None => {
+                    println!("  pop r10");
+                    "r10".to_string()
+                }
+            };
             // ABIの制約のため、RSPを16の倍数にしておく必要がある
-            let sc = scope_count.clone();
+            let sc = *scope_count;
             *scope_count += 1;
             println!("  mov rax, rsp");
             // raxの下位４ビットを切り出す
@@ -100,33 +241,47 @@ fn gen(node: Node, scope_count: &mut u32, fname: &String, stack_size: u32) {
             println!("  and rax, 15");
             println!("  jnz .L.call.{}", sc);
             println!("  mov rax, 0");
-            println!("  call {}", name);
+            println!("  call {}", target);
             println!("  jmp .L.end.{}", sc);
             println!(".L.call.{}:", sc);
             println!("  sub rsp, 8");
             println!("  mov rax, 0");
-            println!("  call {}", name);
+            println!("  call {}", target);
             println!("  add rsp, 8");
             println!(".L.end.{}:", sc);
+            // 戻り値の型より上位のビットは不定
+            truncate(&ty);
             println!("  push rax");
         }
+        ND_FUNC { .. } => {
+            gen_lval(node, scope_count, fname);
+        }
+        ND_MEMZERO { offset, size } => {
+            println!("  lea rdi, [rbp-{}]", offset);
+            println!("  mov rcx, {}", size);
+            println!("  mov al, 0");
+            println!("  rep stosb");
+        }
         ND_BLOCK { stmts } => {
             for stm in stmts {
-                gen(stm, scope_count, fname, stack_size);
-                println!("  pop rax");
+                gen(stm, scope_count, fname);
             }
         }
+        ND_EXPR_STMT { expr } => {
+            gen(*expr, scope_count, fname);
+            println!("  add rsp, 8");
+        }
         ND_IF { cond, cons, alt } => {
-            let sc = scope_count.clone();
+            let sc = *scope_count;
             *scope_count += 1;
-            gen(*cond, scope_count, fname, stack_size);
+            gen(*cond, scope_count, fname);
             println!("  pop rax");
             println!("  cmp rax, 0");
             println!("  je  .Lelse{}", sc);
-            gen(*cons, scope_count, fname, stack_size);
+            gen(*cons, scope_count, fname);
             println!("  jmp .Lend{}", sc);
             println!(".Lelse{}:", sc);
-            gen(*alt, scope_count, fname, stack_size);
+            gen(*alt, scope_count, fname);
             println!(".Lend{}:", sc);
         }
         ND_FOR {
@@ -134,96 +289,336 @@ fn gen(node: Node, scope_count: &mut u32, fname: &String, stack_size: u32) {
             cond,
             inc,
             body,
+            id,
         } => {
-            let sc = scope_count.clone();
+            let sc = *scope_count;
             *scope_count += 1;
-            gen(*init, scope_count, fname, stack_size);
+            gen(*init, scope_count, fname);
             println!(".Lbegin{}:", sc);
-            gen(*cond, scope_count, fname, stack_size);
+            gen(*cond, scope_count, fname);
             println!("  pop rax");
             println!("  cmp rax, 0");
             println!("  je  .Lend{}", sc);
-            gen(*body, scope_count, fname, stack_size);
-            gen(*inc, scope_count, fname, stack_size);
+            gen(*body, scope_count, fname);
+            // continue は増分の式に飛ぶ
+            println!(".L.continue.{}:", id);
+            gen(*inc, scope_count, fname);
             println!("  jmp  .Lbegin{}", sc);
             println!(".Lend{}:", sc);
+            println!(".L.break.{}:", id);
         }
-        ND_WHILE { cond, body } => {
-            let sc = scope_count.clone();
+        ND_WHILE { cond, body, id } => {
+            let sc = *scope_count;
             *scope_count += 1;
             println!(".Lbegin{}:", sc);
-            gen(*cond, scope_count, fname, stack_size);
+            println!(".L.continue.{}:", id);
+            gen(*cond, scope_count, fname);
             println!("  pop rax");
             println!("  cmp rax, 0");
             println!("  je  .Lend{}", sc);
-            gen(*body, scope_count, fname, stack_size);
+            gen(*body, scope_count, fname);
             println!("  jmp  .Lbegin{}", sc);
             println!(".Lend{}:", sc);
+            println!(".L.break.{}:", id);
         }
-        ND_NUM(val) => {
-            println!("  push {}", val);
+        ND_DO { body, cond, id } => {
+            let sc = *scope_count;
+            *scope_count += 1;
+            println!(".Lbegin{}:", sc);
+            gen(*body, scope_count, fname);
+            // continue は条件の判定に飛ぶ
+            println!(".L.continue.{}:", id);
+            gen(*cond, scope_count, fname);
+            println!("  pop rax");
+            println!("  cmp rax, 0");
+            println!("  jne .Lbegin{}", sc);
+            println!(".L.break.{}:", id);
         }
-        ND_LVAR { offset: _ } => {
-            gen_lval(node, stack_size);
+        ND_SWITCH {
+            cond,
+            body,
+            cases,
+            default,
+            id,
+        } => {
+            gen(*cond, scope_count, fname);
+            println!("  pop rax");
+            for (val, label) in cases {
+                println!("  mov rdi, {}", val);
+                println!("  cmp rax, rdi");
+                println!("  je  .L.case.{}", label);
+            }
+            match default {
+                Some(label) => println!("  jmp .L.case.{}", label),
+                None => println!("  jmp .L.break.{}", id),
+            }
+            gen(*body, scope_count, fname);
+            println!(".L.break.{}:", id);
+        }
+        ND_CASE { label, stmt } => {
+            println!(".L.case.{}:", label);
+            gen(*stmt, scope_count, fname);
+        }
+        ND_LABEL { name, stmt } => {
+            println!(".L.label.{}.{}:", fname, name);
+            gen(*stmt, scope_count, fname);
+        }
+        ND_GOTO { name } => {
+            println!("  jmp .L.label.{}.{}", fname, name);
+        }
+        ND_BREAK { id } => {
+            println!("  jmp .L.break.{}", id);
+        }
+        ND_CONTINUE { id } => {
+            println!("  jmp .L.continue.{}", id);
+        }
+        ND_NUM { val, .. } => {
+            println!("  mov rax, {}", val);
+            println!("  push rax");
+        }
+        ND_CAST { expr, ty } => {
+            gen(*expr, scope_count, fname);
             println!("  pop rax");
-            println!("  mov rax, [rax]");
+            if ty == TY_BOOL {
+                // 0以外の値はすべて1にする
+                println!("  cmp rax, 0");
+                println!("  setne al");
+                println!("  movzx rax, al");
+            } else {
+                truncate(&ty);
+            }
             println!("  push rax");
         }
+        ND_LVAR { ref ty, .. } => {
+            let ty = ty.clone();
+            gen_lval(node, scope_count, fname);
+            load(&ty);
+        }
+        ND_GVAR { ref ty, .. } => {
+            let ty = ty.clone();
+            gen_lval(node, scope_count, fname);
+            load(&ty);
+        }
+        ND_MEMBER { ref ty, .. } => {
+            let ty = ty.clone();
+            gen_lval(node, scope_count, fname);
+            load(&ty);
+        }
+        ND_ADDR { expr } => {
+            gen_lval(*expr, scope_count, fname);
+        }
+        ND_DEREF { expr } => {
+            let ty = expr.ty().base().unwrap().clone();
+            gen(*expr, scope_count, fname);
+            load(&ty);
+        }
         ND_ASSIGN { lhs, rhs } => {
-            gen_lval(*lhs, stack_size);
-            gen(*rhs, scope_count, fname, stack_size);
+            let ty = lhs.ty();
+            gen_lval(*lhs, scope_count, fname);
+            gen(*rhs, scope_count, fname);
 
             println!("  pop rdi");
             println!("  pop rax");
-            println!("  mov [rax], rdi");
+            store(&ty);
             println!("  push rdi");
         }
+        ND_COMMA { lhs, rhs } => {
+            gen(*lhs, scope_count, fname);
+            println!("  add rsp, 8");
+            gen(*rhs, scope_count, fname);
+        }
         ND_ADD { lhs, rhs } => {
-            gen_bin_op(*lhs, *rhs, scope_count, fname, stack_size);
+            let ty = lhs.ty().decay();
+            gen_bin_op(*lhs, *rhs, scope_count, fname);
             println!("  add rax, rdi");
+            truncate(&ty);
             println!("  push rax");
         }
         ND_SUB { lhs, rhs } => {
-            gen_bin_op(*lhs, *rhs, scope_count, fname, stack_size);
+            let ty = lhs.ty().decay();
+            gen_bin_op(*lhs, *rhs, scope_count, fname);
             println!("  sub rax, rdi");
+            truncate(&ty);
             println!("  push rax");
         }
         ND_MUL { lhs, rhs } => {
-            gen_bin_op(*lhs, *rhs, scope_count, fname, stack_size);
+            let ty = lhs.ty().decay();
+            gen_bin_op(*lhs, *rhs, scope_count, fname);
             println!("  imul rax, rdi");
+            truncate(&ty);
             println!("  push rax");
         }
         ND_DIV { lhs, rhs } => {
-            gen_bin_op(*lhs, *rhs, scope_count, fname, stack_size);
-            println!("  cqo");
-            println!("  idiv rdi");
+            let ty = lhs.ty().decay();
+            gen_bin_op(*lhs, *rhs, scope_count, fname);
+            if ty.is_unsigned() {
+                println!("  mov rdx, 0");
+                println!("  div rdi");
+            } else {
+                println!("  cqo");
+                println!("  idiv rdi");
+            }
+            truncate(&ty);
+            println!("  push rax");
+        }
+        ND_MOD { lhs, rhs } => {
+            let ty = lhs.ty();
+            gen_bin_op(*lhs, *rhs, scope_count, fname);
+            // 余りはrdxに入る
+            if ty.is_unsigned() {
+                println!("  mov rdx, 0");
+                println!("  div rdi");
+            } else {
+                println!("  cqo");
+                println!("  idiv rdi");
+            }
+            println!("  mov rax, rdx");
+            truncate(&ty);
+            println!("  push rax");
+        }
+        ND_BITAND { lhs, rhs } => {
+            let ty = lhs.ty();
+            gen_bin_op(*lhs, *rhs, scope_count, fname);
+            println!("  and rax, rdi");
+            truncate(&ty);
+            println!("  push rax");
+        }
+        ND_BITOR { lhs, rhs } => {
+            let ty = lhs.ty();
+            gen_bin_op(*lhs, *rhs, scope_count, fname);
+            println!("  or rax, rdi");
+            truncate(&ty);
+            println!("  push rax");
+        }
+        ND_BITXOR { lhs, rhs } => {
+            let ty = lhs.ty();
+            gen_bin_op(*lhs, *rhs, scope_count, fname);
+            println!("  xor rax, rdi");
+            truncate(&ty);
+            println!("  push rax");
+        }
+        ND_BITNOT { expr } => {
+            let ty = expr.ty();
+            gen(*expr, scope_count, fname);
+            println!("  pop rax");
+            println!("  not rax");
+            truncate(&ty);
+            println!("  push rax");
+        }
+        ND_SHL { lhs, rhs } => {
+            let ty = lhs.ty();
+            gen_bin_op(*lhs, *rhs, scope_count, fname);
+            println!("  mov rcx, rdi");
+            println!("  shl rax, cl");
+            truncate(&ty);
+            println!("  push rax");
+        }
+        // 符号付きなら算術シフト、符号なしなら論理シフト
+        ND_SHR { lhs, rhs } => {
+            let ty = lhs.ty();
+            gen_bin_op(*lhs, *rhs, scope_count, fname);
+            println!("  mov rcx, rdi");
+            if ty.is_unsigned() {
+                println!("  shr rax, cl");
+            } else {
+                println!("  sar rax, cl");
+            }
+            truncate(&ty);
+            println!("  push rax");
+        }
+        ND_NOT { expr } => {
+            gen(*expr, scope_count, fname);
+            println!("  pop rax");
+            println!("  cmp rax, 0");
+            println!("  sete al");
+            println!("  movzb rax, al");
             println!("  push rax");
         }
+        // 左辺で結果が決まれば、右辺は評価しない
+        ND_LOGAND { lhs, rhs } => {
+            let sc = *scope_count;
+            *scope_count += 1;
+            gen(*lhs, scope_count, fname);
+            println!("  pop rax");
+            println!("  cmp rax, 0");
+            println!("  je  .L.false.{}", sc);
+            gen(*rhs, scope_count, fname);
+            println!("  pop rax");
+            println!("  cmp rax, 0");
+            println!("  je  .L.false.{}", sc);
+            println!("  push 1");
+            println!("  jmp .L.end.{}", sc);
+            println!(".L.false.{}:", sc);
+            println!("  push 0");
+            println!(".L.end.{}:", sc);
+        }
+        ND_LOGOR { lhs, rhs } => {
+            let sc = *scope_count;
+            *scope_count += 1;
+            gen(*lhs, scope_count, fname);
+            println!("  pop rax");
+            println!("  cmp rax, 0");
+            println!("  jne .L.true.{}", sc);
+            gen(*rhs, scope_count, fname);
+            println!("  pop rax");
+            println!("  cmp rax, 0");
+            println!("  jne .L.true.{}", sc);
+            println!("  push 0");
+            println!("  jmp .L.end.{}", sc);
+            println!(".L.true.{}:", sc);
+            println!("  push 1");
+            println!(".L.end.{}:", sc);
+        }
+        ND_COND {
+            cond, then, els, ..
+        } => {
+            let sc = *scope_count;
+            *scope_count += 1;
+            gen(*cond, scope_count, fname);
+            println!("  pop rax");
+            println!("  cmp rax, 0");
+            println!("  je  .Lelse{}", sc);
+            gen(*then, scope_count, fname);
+            println!("  jmp .Lend{}", sc);
+            println!(".Lelse{}:", sc);
+            gen(*els, scope_count, fname);
+            println!(".Lend{}:", sc);
+        }
         ND_EQ { lhs, rhs } => {
-            gen_bin_op(*lhs, *rhs, scope_count, fname, stack_size);
+            gen_bin_op(*lhs, *rhs, scope_count, fname);
             println!("  cmp rax, rdi");
             println!("  sete al");
             println!("  movzb rax, al");
             println!("  push rax");
         }
         ND_NE { lhs, rhs } => {
-            gen_bin_op(*lhs, *rhs, scope_count, fname, stack_size);
+            gen_bin_op(*lhs, *rhs, scope_count, fname);
             println!("  cmp rax, rdi");
             println!("  setne al");
             println!("  movzb rax, al");
             println!("  push rax");
         }
         ND_LT { lhs, rhs } => {
-            gen_bin_op(*lhs, *rhs, scope_count, fname, stack_size);
+            let ty = lhs.ty().decay();
+            gen_bin_op(*lhs, *rhs, scope_count, fname);
             println!("  cmp rax, rdi");
-            println!("  setl al");
+            if ty.is_unsigned() {
+                println!("  setb al");
+            } else {
+                println!("  setl al");
+            }
             println!("  movzb rax, al");
             println!("  push rax");
         }
         ND_LE { lhs, rhs } => {
-            gen_bin_op(*lhs, *rhs, scope_count, fname, stack_size);
+            let ty = lhs.ty().decay();
+            gen_bin_op(*lhs, *rhs, scope_count, fname);
             println!("  cmp rax, rdi");
-            println!("  setle al");
+            if ty.is_unsigned() {
+                println!("  setbe al");
+            } else {
+                println!("  setle al");
+            }
             println!("  movzb rax, al");
             println!("  push rax");
         }
@@ -1,4 +1,6 @@
 #![allow(non_camel_case_types)]
+#![allow(clippy::needless_return)]
 pub mod codegen;
 pub mod parse;
 pub mod tokenize;
+pub mod types;
@@ -2,8 +2,13 @@ use crate::tokenize::TokenKind::{
     TK_FOR, TK_IDENT, TK_IF, TK_NUM, TK_RESERVED, TK_RETURN, TK_WHILE,
 };
 use crate::tokenize::{Token, TokenKind};
+use crate::types::Type::*;
+use crate::types::{align_to, common_type, composite_type, ptrdiff_t, StructRef, Type};
 use std::collections::VecDeque;
 
+// レジスタで渡せる引数の数
+const ARG_MAX: usize = 6;
+
 #[derive(Clone, Debug)]
 pub enum Node {
     ND_NOTHING,
@@ -23,14 +28,71 @@ pub enum Node {
         lhs: Box<Node>,
         rhs: Box<Node>,
     },
+    ND_MOD {
+        lhs: Box<Node>,
+        rhs: Box<Node>,
+    },
+    ND_BITAND {
+        lhs: Box<Node>,
+        rhs: Box<Node>,
+    },
+    ND_BITOR {
+        lhs: Box<Node>,
+        rhs: Box<Node>,
+    },
+    ND_BITXOR {
+        lhs: Box<Node>,
+        rhs: Box<Node>,
+    },
+    ND_BITNOT {
+        expr: Box<Node>,
+    },
+    ND_SHL {
+        lhs: Box<Node>,
+        rhs: Box<Node>,
+    },
+    ND_SHR {
+        lhs: Box<Node>,
+        rhs: Box<Node>,
+    },
     ND_ASSIGN {
         lhs: Box<Node>,
         rhs: Box<Node>,
     },
+    // 左辺を評価して値を捨て、右辺の値を結果にする
+    ND_COMMA {
+        lhs: Box<Node>,
+        rhs: Box<Node>,
+    },
+    // 16バイトより大きな境界に揃える変数は、rbp-offset を実行時に align に切り上げた位置に置く
     ND_LVAR {
         offset: u32,
+        ty: Type,
+        align: u32,
+    },
+    ND_GVAR {
+        name: String,
+        ty: Type,
+    },
+    ND_NUM {
+        val: i64,
+        ty: Type,
+    },
+    ND_CAST {
+        expr: Box<Node>,
+        ty: Type,
+    },
+    ND_ADDR {
+        expr: Box<Node>,
+    },
+    ND_DEREF {
+        expr: Box<Node>,
+    },
+    ND_MEMBER {
+        expr: Box<Node>,
+        offset: u32,
+        ty: Type,
     },
-    ND_NUM(u32),
     ND_EQ {
         lhs: Box<Node>,
         rhs: Box<Node>,
@@ -47,6 +109,24 @@ pub enum Node {
         lhs: Box<Node>,
         rhs: Box<Node>,
     },
+    ND_NOT {
+        expr: Box<Node>,
+    },
+    ND_LOGAND {
+        lhs: Box<Node>,
+        rhs: Box<Node>,
+    },
+    ND_LOGOR {
+        lhs: Box<Node>,
+        rhs: Box<Node>,
+    },
+    // cond ? then : els
+    ND_COND {
+        cond: Box<Node>,
+        then: Box<Node>,
+        els: Box<Node>,
+        ty: Type,
+    },
     ND_RETURN {
         ret: Box<Node>,
     },
@@ -55,46 +135,507 @@ pub enum Node {
         cons: Box<Node>,
         alt: Box<Node>,
     },
+    // id はループや switch 文ごとに振った番号で、break と continue の飛び先を表す
     ND_WHILE {
         cond: Box<Node>,
         body: Box<Node>,
+        id: u32,
     },
     ND_BLOCK {
         stmts: Vec<Node>,
     },
+    ND_EXPR_STMT {
+        expr: Box<Node>,
+    },
     ND_FOR {
         init: Box<Node>,
         cond: Box<Node>,
         inc: Box<Node>,
         body: Box<Node>,
+        id: u32,
     },
-    ND_FUNCALL {
+    // do body while (cond);
+    ND_DO {
+        body: Box<Node>,
+        cond: Box<Node>,
+        id: u32,
+    },
+    // cases の値と一致すれば対応する ND_CASE に、どれとも一致しなければ default に飛ぶ
+    ND_SWITCH {
+        cond: Box<Node>,
+        body: Box<Node>,
+        cases: Vec<(i64, u32)>,
+        default: Option<u32>,
+        id: u32,
+    },
+    // case と default のラベルを付けた文
+    ND_CASE {
+        label: u32,
+        stmt: Box<Node>,
+    },
+    // ラベルの名前は関数ごとに別
+    ND_LABEL {
+        name: String,
+        stmt: Box<Node>,
+    },
+    ND_GOTO {
+        name: String,
+    },
+    ND_BREAK {
+        id: u32,
+    },
+    ND_CONTINUE {
+        id: u32,
+    },
+    // 関数名 (関数のアドレス)
+    ND_FUNC {
         name: String,
+        ty: Type,
+    },
+    ND_FUNCALL {
+        func: Box<Node>,
         args: Vec<Node>,
+        ty: Type,
     },
     ND_FUNCTION {
         name: String,
         body: Vec<Node>,
         parameters: VecDeque<Node>,
         stack_size: u32,
+        is_static: bool,
+    },
+    ND_GVAR_DEF {
+        name: String,
+        ty: Type,
+        align: u32,
+        init: Option<StaticData>,
+        is_static: bool,
+    },
+    // ローカル変数の領域を0で埋める
+    ND_MEMZERO {
+        offset: u32,
+        size: u32,
     },
 }
 
+// グローバル変数の初期値
+#[derive(Clone, Debug)]
+pub struct StaticData {
+    pub bytes: Vec<u8>,
+    // アドレスで決まる値は、アセンブラとリンカに解決してもらう
+    pub relocs: Vec<Reloc>,
+}
+
+// bytes の offset からの8バイトを label + addend のアドレスにする
+#[derive(Clone, Debug)]
+pub struct Reloc {
+    pub offset: usize,
+    pub label: String,
+    pub addend: i64,
+}
+
 use Node::*;
 
+impl Node {
+    // 式の型
+    pub fn ty(&self) -> Type {
+        match self {
+            ND_NUM { ty, .. }
+            | ND_CAST { ty, .. }
+            | ND_LVAR { ty, .. }
+            | ND_GVAR { ty, .. }
+            | ND_MEMBER { ty, .. }
+            | ND_COND { ty, .. }
+            | ND_FUNC { ty, .. }
+            | ND_FUNCALL { ty, .. } => ty.clone(),
+            ND_ADDR { expr } => expr.ty().pointer_to(),
+            ND_DEREF { expr } => expr.ty().base().unwrap().clone(),
+            // 左辺の型は一度だけ計算する (引き算が連なると計算量が指数的に増えるため)
+            ND_SUB { lhs, rhs } => {
+                let ty = lhs.ty().decay();
+                if ty.base().is_some() && rhs.ty().base().is_some() {
+                    return ptrdiff_t();
+                }
+                return ty;
+            }
+            // 両辺は usual_arith_conv で同じ型に揃えてある
+            ND_ADD { lhs, .. } | ND_MUL { lhs, .. } | ND_DIV { lhs, .. } => lhs.ty().decay(),
+            ND_MOD { lhs, .. }
+            | ND_BITAND { lhs, .. }
+            | ND_BITOR { lhs, .. }
+            | ND_BITXOR { lhs, .. }
+            | ND_SHL { lhs, .. }
+            | ND_SHR { lhs, .. } => lhs.ty(),
+            ND_BITNOT { expr } => expr.ty(),
+            ND_ASSIGN { lhs, .. } => lhs.ty(),
+            ND_COMMA { rhs, .. } => rhs.ty(),
+            ND_EQ { .. } | ND_NE { .. } | ND_LT { .. } | ND_LE { .. } => TY_INT,
+            ND_NOT { .. } | ND_LOGAND { .. } | ND_LOGOR { .. } => TY_INT,
+            _ => unreachable!(),
+        }
+    }
+}
+
+// スコープに登録される名前
 #[derive(Clone, Debug)]
-struct LVar {
+enum Symbol {
+    SYM_LVAR { offset: u32, ty: Type, align: u32 },
+    SYM_GVAR { ty: Type },
+    // 列挙定数
+    SYM_ENUM_CONST { val: i64 },
+    SYM_TYPEDEF { ty: Type },
+    SYM_FUNC {
+        ty: Type,
+        is_static: bool,
+        is_defined: bool,
+    },
+}
+
+// 宣言の記憶域クラス指定子
+#[derive(Default)]
+struct VarAttr {
+    is_typedef: bool,
+    is_static: bool,
+    is_extern: bool,
+    // _Alignas で指定したアライメント (指定がなければ0)
+    align: u32,
+}
+
+impl VarAttr {
+    // 宣言する変数のアライメント
+    fn align_of(&self, name: &str, ty: &Type) -> u32 {
+        if self.align == 0 {
+            return ty.align();
+        }
+        if self.align < ty.align() {
+            eprintln!("{}のアライメントを型のアライメントより小さくすることはできません", name);
+            std::process::exit(1);
+        }
+        return self.align;
+    }
+}
+
+// ファイルスコープの変数
+struct GlobalVar {
     name: String,
+    ty: Type,
+    is_static: bool,
+    // _Alignas で指定したアライメント (指定がなければ0)
+    align: u32,
+    // extern でない宣言があれば、この翻訳単位で領域を確保する
+    is_defined: bool,
+    // 初期値 (なければ0で初期化する)
+    init: Option<StaticData>,
+}
+
+use Symbol::*;
+
+// パース中の switch 文に現れた case と default のラベル
+struct SwitchCases {
+    cond_ty: Type,
+    cases: Vec<(i64, u32)>,
+    default: Option<u32>,
+}
+
+// ブロック1つ分の名前とタグ
+struct Scope {
+    vars: Vec<(String, Symbol)>,
+    tags: Vec<(String, Type)>,
+    // スコープに入ったときのフレームの使用量
+    frame_base: u32,
+}
+
+// パース中の状態
+// scopes の先頭はファイルスコープ
+struct Env {
+    scopes: Vec<Scope>,
+    // 現在使用しているフレームのサイズ
     offset: u32,
+    // 関数全体で必要なフレームのサイズ
+    stack_size: u32,
+    // パース中の関数の戻り値の型
+    ret_ty: Type,
+    // 最後に読んだ引数リストの引数名 (関数定義で使う)
+    param_names: Vec<Option<Token>>,
+    globals: Vec<GlobalVar>,
+    // これまでに振ったラベルの番号
+    label_count: u32,
+    // break と continue で抜ける最も内側のループや switch 文
+    brk: Option<u32>,
+    cont: Option<u32>,
+    // 最も内側の switch 文
+    switch: Option<SwitchCases>,
+    // パース中の関数で定義したラベルと、goto で飛ぶラベル
+    labels: Vec<String>,
+    gotos: Vec<String>,
 }
 
-fn find_lvar(token: &Token, lvars: &VecDeque<LVar>) -> Option<LVar> {
-    for lvar in lvars {
-        if token.str == lvar.name {
-            return Some(lvar.clone());
+impl Env {
+    fn new() -> Env {
+        let mut env = Env {
+            scopes: Vec::new(),
+            offset: 0,
+            stack_size: 0,
+            ret_ty: TY_INT,
+            param_names: Vec::new(),
+            globals: Vec::new(),
+            label_count: 0,
+            brk: None,
+            cont: None,
+            switch: None,
+            labels: Vec::new(),
+            gotos: Vec::new(),
+        };
+        env.enter_scope();
+        return env;
+    }
+
+    fn enter_scope(&mut self) {
+        self.scopes.push(Scope {
+            vars: Vec::new(),
+            tags: Vec::new(),
+            frame_base: self.offset,
+        });
+    }
+
+    // スコープを抜けると、その中の変数の領域は兄弟のスコープで再利用される
+    fn leave_scope(&mut self) {
+        let scope = self.scopes.pop().unwrap();
+        self.offset = scope.frame_base;
+    }
+
+    // 内側のスコープから順に探す
+    fn find_var(&self, name: &str) -> Option<&Symbol> {
+        for scope in self.scopes.iter().rev() {
+            for (var, sym) in scope.vars.iter().rev() {
+                if var == name {
+                    return Some(sym);
+                }
+            }
         }
+        return None;
+    }
+
+    fn find_tag(&self, name: &str) -> Option<&Type> {
+        for scope in self.scopes.iter().rev() {
+            for (tag, ty) in scope.tags.iter().rev() {
+                if tag == name {
+                    return Some(ty);
+                }
+            }
+        }
+        return None;
+    }
+
+    fn find_tag_in_current_scope(&self, name: &str) -> Option<&Type> {
+        let scope = self.scopes.last().unwrap();
+        return scope.tags.iter().find(|(tag, _)| tag == name).map(|(_, ty)| ty);
+    }
+
+    fn declare_tag(&mut self, name: String, ty: Type) {
+        self.scopes.last_mut().unwrap().tags.push((name, ty));
+    }
+
+    fn declare(&mut self, name: String, sym: Symbol) {
+        let scope = self.scopes.last_mut().unwrap();
+        if scope.vars.iter().any(|(var, _)| *var == name) {
+            eprintln!("{}はすでに宣言されています", name);
+            std::process::exit(1);
+        }
+        scope.vars.push((name, sym));
+    }
+
+    // 関数は互換性のある型でなら何度でも宣言できる
+    // 定義は1回だけで、一度staticで宣言した関数はずっとstatic
+    // staticでない宣言の後でstaticと宣言することはできない
+    fn declare_func(&mut self, name: String, ty: Type, is_static: bool, is_definition: bool) {
+        let scope = self.scopes.last_mut().unwrap();
+        match scope.vars.iter_mut().find(|(var, _)| *var == name) {
+            Some((
+                _,
+                SYM_FUNC {
+                    ty: prev_ty,
+                    is_static: prev_static,
+                    is_defined,
+                },
+            )) => {
+                match composite_type(prev_ty, &ty) {
+                    Some(composite) => *prev_ty = composite,
+                    None => {
+                        eprintln!("関数{}の型が以前の宣言と一致しません", name);
+                        std::process::exit(1);
+                    }
+                }
+                if *is_defined && is_definition {
+                    eprintln!("関数{}はすでに定義されています", name);
+                    std::process::exit(1);
+                }
+                if is_static && !*prev_static {
+                    eprintln!("関数{}はstaticでない宣言の後でstaticと宣言されています", name);
+                    std::process::exit(1);
+                }
+                *is_defined |= is_definition;
+            }
+            Some(_) => {
+                eprintln!("{}はすでに宣言されています", name);
+                std::process::exit(1);
+            }
+            None => scope.vars.push((
+                name,
+                SYM_FUNC {
+                    ty,
+                    is_static,
+                    is_defined: is_definition,
+                },
+            )),
+        }
+    }
+
+    // グローバル変数は同じ型でなら何度でも宣言できる (仮定義)
+    // 初期値を与えられるのは1回だけ
+    fn declare_gvar(&mut self, name: String, ty: Type, attr: &VarAttr, init: Option<StaticData>) {
+        let is_defined = !attr.is_extern || init.is_some();
+        // 長さを省略した配列の仮定義は、最後まで長さが決まらなければ要素数1になる
+        let is_tentative = init.is_none()
+            && matches!(&ty, TY_ARRAY { base, len: None } if !base.is_incomplete());
+        if is_defined && !is_tentative && ty.is_incomplete() {
+            eprintln!("{}の型は不完全です", name);
+            std::process::exit(1);
+        }
+        let mut ty = ty;
+        match self.globals.iter_mut().find(|gvar| gvar.name == name) {
+            Some(gvar) => {
+                // extern int a[]; と int a[3]; は同じ変数の宣言
+                match (&gvar.ty, &ty) {
+                    (TY_ARRAY { base: b1, len: None }, TY_ARRAY { base: b2, .. }) if b1 == b2 => {
+                        gvar.ty = ty.clone();
+                    }
+                    (TY_ARRAY { base: b1, .. }, TY_ARRAY { base: b2, len: None }) if b1 == b2 => {
+                        ty = gvar.ty.clone();
+                    }
+                    (prev, ty) if prev == ty => {}
+                    _ => {
+                        eprintln!("{}の型が以前の宣言と一致しません", name);
+                        std::process::exit(1);
+                    }
+                }
+                if gvar.init.is_some() && init.is_some() {
+                    eprintln!("{}はすでに定義されています", name);
+                    std::process::exit(1);
+                }
+                gvar.is_static |= attr.is_static;
+                gvar.align = gvar.align.max(attr.align);
+                gvar.is_defined |= is_defined;
+                if init.is_some() {
+                    gvar.init = init;
+                }
+            }
+            None => self.globals.push(GlobalVar {
+                name: name.clone(),
+                ty: ty.clone(),
+                is_static: attr.is_static,
+                align: attr.align,
+                is_defined,
+                init,
+            }),
+        }
+        // ブロックスコープの extern 宣言は、そのスコープからファイルスコープの変数を見えるようにする
+        let scope = self.scopes.last_mut().unwrap();
+        match scope.vars.iter_mut().find(|(var, _)| *var == name) {
+            Some((_, SYM_GVAR { ty: prev })) => *prev = ty,
+            Some(_) => {
+                eprintln!("{}はすでに宣言されています", name);
+                std::process::exit(1);
+            }
+            None => scope.vars.push((name, SYM_GVAR { ty })),
+        }
+    }
+
+    // 宣言されていない関数を呼び出すと、intを返す関数がファイルスコープで暗黙に宣言される
+    fn declare_implicit_func(&mut self, name: String) -> Type {
+        eprintln!("警告: 関数{}は暗黙に宣言されています", name);
+        let ty = TY_INT.func_returning(None);
+        self.scopes[0].vars.push((
+            name,
+            SYM_FUNC {
+                ty: ty.clone(),
+                is_static: false,
+                is_defined: false,
+            },
+        ));
+        return ty;
+    }
+
+    fn is_static_func(&self, name: &str) -> bool {
+        return matches!(self.find_var(name), Some(SYM_FUNC { is_static: true, .. }));
+    }
+
+    // フレームに領域を確保して、rbpからのオフセットを返す
+    // rbpは16の倍数なので、オフセットをアライメントの倍数にすればアドレスも揃う
+    fn declare_lvar(&mut self, name: String, ty: Type, align: u32) -> u32 {
+        if ty.is_incomplete() {
+            eprintln!("{}の型は不完全です", name);
+            std::process::exit(1);
+        }
+        // rbpは16バイト境界に揃っているので、それより大きな境界に揃える変数は
+        // 切り上げで動く分だけ余分に領域を取っておく
+        let offset = if align > 16 {
+            self.alloc_frame(ty.size().saturating_add(align - 16), 16)
+        } else {
+            self.alloc_frame(ty.size(), align)
+        };
+        self.declare(name, SYM_LVAR { offset, ty, align });
+        return offset;
+    }
+
+    // rbpからのオフセットは32ビットの符号付き整数で表す
+    fn alloc_frame(&mut self, size: u32, align: u32) -> u32 {
+        let end = self.offset.checked_add(size).filter(|end| *end <= i32::MAX as u32 - 16);
+        let end = match end {
+            Some(end) => end,
+            None => {
+                eprintln!("ローカル変数の領域が大きすぎます");
+                std::process::exit(1);
+            }
+        };
+        self.offset = align_to(end, align);
+        if self.stack_size < self.offset {
+            self.stack_size = self.offset;
+        }
+        return self.offset;
+    }
+
+    fn declare_label(&mut self, name: String) {
+        if self.labels.contains(&name) {
+            eprintln!("ラベル{}が重複しています", name);
+            std::process::exit(1);
+        }
+        self.labels.push(name);
+    }
+
+    // goto で飛ぶラベルは関数の後ろで定義されていてもよいので、関数の終わりで確かめる
+    fn resolve_gotos(&mut self) {
+        for name in self.gotos.iter() {
+            if !self.labels.contains(name) {
+                eprintln!("ラベル{}は定義されていません", name);
+                std::process::exit(1);
+            }
+        }
+        self.labels.clear();
+        self.gotos.clear();
+    }
+
+    fn new_label(&mut self) -> u32 {
+        self.label_count += 1;
+        return self.label_count;
+    }
+
+    // 式の途中で使う名前のない変数
+    fn new_temp(&mut self, ty: Type) -> Node {
+        let align = ty.align();
+        let offset = self.alloc_frame(ty.size(), align);
+        return ND_LVAR { offset, ty, align };
     }
-    return None;
 }
 
 fn consume(tokens: &mut VecDeque<Token>, op: &str) -> bool {
@@ -110,6 +651,13 @@ fn consume(tokens: &mut VecDeque<Token>, op: &str) -> bool {
     return false;
 }
 
+fn peek(tokens: &VecDeque<Token>, op: &str) -> bool {
+    if let Some(token) = tokens.front() {
+        return token.kind == TK_RESERVED && token.str == op;
+    }
+    return false;
+}
+
 fn consume_tk(tokens: &mut VecDeque<Token>, tkind: TokenKind) -> Option<Token> {
     if let Some(token) = tokens.front() {
         if token.kind == tkind {
@@ -136,7 +684,7 @@ fn expect(tokens: &mut VecDeque<Token>, op: &str) {
     std::process::exit(1);
 }
 
-fn expect_number(tokens: &mut VecDeque<Token>) -> Result<u32, String> {
+fn expect_number(tokens: &mut VecDeque<Token>) -> Result<u64, String> {
     if let Some(token) = tokens.front() {
         return match token.kind {
             TK_NUM => {
@@ -150,33 +698,171 @@ fn expect_number(tokens: &mut VecDeque<Token>) -> Result<u32, String> {
     return Err(String::from("二項演算子が文末に来ることはありません"));
 }
 
-// program = function*
+// program = (function | declspec (typedef | function-definition | global-declarators))*
 pub fn program(tokens: &mut VecDeque<Token>) -> Vec<Node> {
+    let mut env = Env::new();
     let mut nodes: Vec<Node> = Vec::new();
     while !tokens.is_empty() {
-        nodes.push(function(tokens));
+        if !is_typename(tokens, &env) {
+            // 戻り値の型と引数の型を省略した関数定義
+            nodes.push(function(tokens, &mut env));
+            continue;
+        }
+        let mut attr = VarAttr::default();
+        let base = declspec(tokens, &mut env, Some(&mut attr));
+        if attr.is_typedef {
+            parse_typedef(tokens, base, &mut env);
+            continue;
+        }
+        if consume(tokens, ";") {
+            continue;
+        }
+        let (ty, token) = declarator(tokens, base.clone(), &mut env);
+        if ty.is_func() && peek(tokens, "{") {
+            nodes.push(function_definition(tokens, token, ty, &attr, &mut env));
+            continue;
+        }
+        global_declarator(tokens, token, ty, &attr, &mut env);
+        while consume(tokens, ",") {
+            let (ty, token) = declarator(tokens, base.clone(), &mut env);
+            global_declarator(tokens, token, ty, &attr, &mut env);
+        }
+        expect(tokens, ";");
+    }
+    // 仮定義をまとめて、この翻訳単位で定義するグローバル変数を出力する
+    for mut gvar in env.globals {
+        if gvar.is_defined {
+            if let TY_ARRAY { len: len @ None, .. } = &mut gvar.ty {
+                *len = Some(1);
+            }
+            nodes.push(ND_GVAR_DEF {
+                name: gvar.name,
+                align: gvar.align.max(gvar.ty.align()),
+                ty: gvar.ty,
+                init: gvar.init,
+                is_static: gvar.is_static,
+            });
+        }
     }
     return nodes;
 }
 
-// function = ident "(" params? ")" "{" stmt* "}"
-fn function(tokens: &mut VecDeque<Token>) -> Node {
-    let mut lvars: VecDeque<LVar> = VecDeque::new();
+// global-declarator = declarator ("=" initializer)?
+fn global_declarator(
+    tokens: &mut VecDeque<Token>,
+    token: Token,
+    ty: Type,
+    attr: &VarAttr,
+    env: &mut Env,
+) {
+    if ty.is_func() {
+        env.declare_func(token.str, ty, attr.is_static, false);
+        return;
+    }
+    if !consume(tokens, "=") {
+        attr.align_of(&token.str, &ty);
+        env.declare_gvar(token.str, ty, attr, None);
+        return;
+    }
+    // void *p = &p; のように初期化式で自分自身を参照できるように、先に宣言だけしておく
+    let decl = VarAttr {
+        is_static: attr.is_static,
+        is_extern: true,
+        ..VarAttr::default()
+    };
+    env.declare_gvar(token.str.clone(), ty.clone(), &decl, None);
+    let init = initializer(tokens, ty, env);
+    if init.ty.is_incomplete() {
+        eprintln!("{}の型は不完全です", token.str);
+        std::process::exit(1);
+    }
+    let mut data = StaticData {
+        bytes: vec![0; init.ty.size() as usize],
+        relocs: Vec::new(),
+    };
+    write_static_data(&init, 0, &mut data);
+    attr.align_of(&token.str, &init.ty);
+    env.declare_gvar(token.str, init.ty, attr, Some(data));
+}
+
+// function-definition = declspec declarator "{" compound-stmt
+fn function_definition(
+    tokens: &mut VecDeque<Token>,
+    token: Token,
+    ty: Type,
+    attr: &VarAttr,
+    env: &mut Env,
+) -> Node {
+    let names = std::mem::take(&mut env.param_names);
+    let (ret, params) = match ty.clone() {
+        TY_FUNC { ret, params } => (*ret, params.unwrap_or_default()),
+        _ => unreachable!(),
+    };
+    if params.len() > ARG_MAX {
+        eprintln!("関数{}の引数が多すぎます", token.str);
+        std::process::exit(1);
+    }
+    env.declare_func(token.str.clone(), ty, attr.is_static, true);
+    env.offset = 0;
+    env.stack_size = 0;
+    env.ret_ty = ret;
+    // 引数と関数本体の一番外側のブロックは同じスコープに属する
+    env.enter_scope();
+    let mut parameters: VecDeque<Node> = VecDeque::new();
+    for (ty, name) in params.into_iter().zip(names) {
+        match name {
+            Some(name) => {
+                let align = ty.align();
+                parameters.push_back(declare_lvar(name, ty, align, env));
+            }
+            None => {
+                eprintln!("関数{}の定義で引数名が省略されています", token.str);
+                std::process::exit(1);
+            }
+        }
+    }
+    expect(tokens, "{");
+    let body = compound_stmt(tokens, env);
+    env.leave_scope();
+    env.resolve_gotos();
+    return ND_FUNCTION {
+        is_static: env.is_static_func(&token.str),
+        name: token.str,
+        body,
+        parameters,
+        stack_size: align_to(env.stack_size, 16),
+    };
+}
+
+// function = ident "(" params? ")" "{" compound-stmt
+//
+// 戻り値と引数はすべてintとみなす
+fn function(tokens: &mut VecDeque<Token>, env: &mut Env) -> Node {
+    env.offset = 0;
+    env.stack_size = 0;
+    env.ret_ty = TY_INT;
     if let Some(token) = consume_tk(tokens, TK_IDENT) {
+        // 再帰呼び出しできるように、本体より先に関数名を登録する
+        env.declare_func(token.str.clone(), TY_INT.func_returning(None), false, true);
+        // 引数と関数本体の一番外側のブロックは同じスコープに属する
+        env.enter_scope();
         expect(tokens, "(");
-        let parameters = params(tokens, &mut lvars);
+        let parameters = params(tokens, env);
         expect(tokens, ")");
-        expect(tokens, "{");
-        let mut body: Vec<Node> = Vec::new();
-        while !consume(tokens, "}") {
-            body.push(stmt(tokens, &mut lvars));
+        if parameters.len() > ARG_MAX {
+            eprintln!("関数{}の引数が多すぎます", token.str);
+            std::process::exit(1);
         }
-        let stack_size = 8 * lvars.len() as u32;
+        expect(tokens, "{");
+        let body = compound_stmt(tokens, env);
+        env.leave_scope();
+        env.resolve_gotos();
         return ND_FUNCTION {
+            is_static: env.is_static_func(&token.str),
             name: token.str,
             body,
             parameters,
-            stack_size,
+            stack_size: align_to(env.stack_size, 16),
         };
     } else {
         eprintln!("関数名を期待しましたが、ありませんでした");
@@ -185,13 +871,13 @@ fn function(tokens: &mut VecDeque<Token>) -> Node {
 }
 
 // params   = ident ("," ident)*
-fn params(tokens: &mut VecDeque<Token>, lvars: &mut VecDeque<LVar>) -> VecDeque<Node> {
+fn params(tokens: &mut VecDeque<Token>, env: &mut Env) -> VecDeque<Node> {
     let mut prms: VecDeque<Node> = VecDeque::new();
     if let Some(token) = consume_tk(tokens, TK_IDENT) {
-        prms.push_back(local_var(token, lvars));
+        prms.push_back(declare_lvar(token, TY_INT, 4, env));
         while consume(tokens, ",") {
             if let Some(token) = consume_tk(tokens, TK_IDENT) {
-                prms.push_back(local_var(token, lvars))
+                prms.push_back(declare_lvar(token, TY_INT, 4, env))
             }
         }
         return prms;
@@ -200,142 +886,1605 @@ fn params(tokens: &mut VecDeque<Token>, lvars: &mut VecDeque<LVar>) -> VecDeque<
     }
 }
 
-// stmt = expr ";"
-//   | "{" stmt* "}"
-//   | "if" "(" expr ")" stmt ("else" stmt)?
-//   | "while" "(" expr ")" stmt
-//   | "for" "(" expr? ";" expr? ";" expr? ";" ")" stmt
-//   | "return" expr ";"
-fn stmt(tokens: &mut VecDeque<Token>, lvars: &mut VecDeque<LVar>) -> Node {
-    let node = if let Some(_token) = consume_tk(tokens, TK_RETURN) {
-        let expr_node = expr(tokens, lvars);
-        expect(tokens, ";");
-        ND_RETURN {
-            ret: Box::new(expr_node),
-        }
-    } else if let Some(_token) = consume_tk(tokens, TK_IF) {
-        expect(tokens, "(");
-        let cond = Box::new(expr(tokens, lvars));
-        expect(tokens, ")");
-        let cons = Box::new(stmt(tokens, lvars));
-        let alt = Box::new(if consume(tokens, "else") {
-            stmt(tokens, lvars)
-        } else {
-            ND_NOTHING
-        });
-        ND_IF { cond, cons, alt }
-    } else if let Some(_token) = consume_tk(tokens, TK_WHILE) {
-        expect(tokens, "(");
-        let cond = Box::new(expr(tokens, lvars));
-        expect(tokens, ")");
-        let body = Box::new(stmt(tokens, lvars));
-        ND_WHILE { cond, body }
-    } else if consume(tokens, "{") {
-        let mut stmts: Vec<Node> = Vec::new();
-        while !consume(tokens, "}") {
-            stmts.push(stmt(tokens, lvars));
-        }
-        ND_BLOCK { stmts }
-    } else if let Some(_token) = consume_tk(tokens, TK_FOR) {
-        expect(tokens, "(");
-        let init = Box::new(if consume(tokens, ";") {
-            ND_NOTHING
-        } else {
-            let nd = expr(tokens, lvars);
-            expect(tokens, ";");
-            nd
-        });
-        let cond = Box::new(if consume(tokens, ";") {
-            ND_NOTHING
+// compound-stmt = (declaration | stmt)* "}"
+fn compound_stmt(tokens: &mut VecDeque<Token>, env: &mut Env) -> Vec<Node> {
+    let mut stmts: Vec<Node> = Vec::new();
+    while !consume(tokens, "}") {
+        if is_typename(tokens, env) {
+            stmts.push(declaration(tokens, env));
         } else {
-            let nd = expr(tokens, lvars);
-            expect(tokens, ";");
-            nd
-        });
-        let inc = Box::new(if consume(tokens, ";") {
-            ND_NOTHING
-        } else {
-            let nd = expr(tokens, lvars);
-            expect(tokens, ";");
-            nd
-        });
-        expect(tokens, ")");
-        let body = Box::new(stmt(tokens, lvars));
-        ND_FOR {
-            init,
-            cond,
-            inc,
-            body,
-        }
-    } else {
-        let nd = expr(tokens, lvars);
-        expect(tokens, ";");
-        nd
-    };
-    return node;
-}
-
-// expr = assign
-fn expr(tokens: &mut VecDeque<Token>, lvars: &mut VecDeque<LVar>) -> Node {
-    return assign(tokens, lvars);
-}
-
-// assign = equality ("=" assign)?
-fn assign(tokens: &mut VecDeque<Token>, lvars: &mut VecDeque<LVar>) -> Node {
-    let mut node: Node = equality(tokens, lvars);
-    if consume(tokens, "=") {
-        node = ND_ASSIGN {
-            lhs: Box::new(node),
-            rhs: Box::new(assign(tokens, lvars)),
+            stmts.push(stmt(tokens, env));
         }
     }
-    return node;
+    return stmts;
 }
 
-// equality   = relational ( "==" relational | "!=" relational)*
-fn equality(tokens: &mut VecDeque<Token>, lvars: &mut VecDeque<LVar>) -> Node {
-    let mut node: Node = relational(tokens, lvars);
+// declaration = declspec (typedef | init-declarator ("," init-declarator)* ";")
+// init-declarator = declarator ("=" assign)?
+fn declaration(tokens: &mut VecDeque<Token>, env: &mut Env) -> Node {
+    let mut attr = VarAttr::default();
+    let base = declspec(tokens, env, Some(&mut attr));
+    let mut stmts: Vec<Node> = Vec::new();
+    if attr.is_typedef {
+        parse_typedef(tokens, base, env);
+        return ND_BLOCK { stmts };
+    }
+    // struct P { ... }; のように型だけを宣言することもできる
+    if consume(tokens, ";") {
+        return ND_BLOCK { stmts };
+    }
     loop {
-        if consume(tokens, "==") {
-            node = ND_EQ {
-                lhs: Box::new(node),
-                rhs: Box::new(relational(tokens, lvars)),
+        let (ty, token) = declarator(tokens, base.clone(), env);
+        if ty.is_func() {
+            env.declare_func(token.str, ty, attr.is_static, false);
+            if !consume(tokens, ",") {
+                break;
             }
-        } else if consume(tokens, "!=") {
-            node = ND_NE {
-                lhs: Box::new(node),
-                rhs: Box::new(relational(tokens, lvars)),
+            continue;
+        }
+        if attr.is_extern {
+            env.declare_gvar(token.str, ty, &attr, None);
+            if !consume(tokens, ",") {
+                break;
             }
+            continue;
+        }
+        if attr.is_static {
+            eprintln!("staticなローカル変数{}は宣言できません", token.str);
+            std::process::exit(1);
+        }
+        if consume(tokens, "=") {
+            // 長さを省略した配列は、初期化子を読んで長さが決まってから領域を確保する
+            let (var, init) = if ty.is_array() && ty.is_incomplete() {
+                let init = initializer(tokens, ty, env);
+                let align = attr.align_of(&token.str, &init.ty);
+                (declare_lvar(token, init.ty.clone(), align, env), init)
+            } else {
+                let align = attr.align_of(&token.str, &ty);
+                let var = declare_lvar(token, ty.clone(), align, env);
+                (var, initializer(tokens, ty, env))
+            };
+            stmts.append(&mut local_init(var, &init));
         } else {
-            return node;
+            let align = attr.align_of(&token.str, &ty);
+            declare_lvar(token, ty, align, env);
+        }
+        if !consume(tokens, ",") {
+            break;
         }
     }
+    expect(tokens, ";");
+    return ND_BLOCK { stmts };
 }
 
-// relational = add("<" add | "<=" add | ">" add | ">=" add)*
-fn relational(tokens: &mut VecDeque<Token>, lvars: &mut VecDeque<LVar>) -> Node {
-    let mut node: Node = add(tokens, lvars);
-    loop {
-        if consume(tokens, "<") {
-            node = ND_LT {
-                lhs: Box::new(node),
-                rhs: Box::new(add(tokens, lvars)),
+// 変数の型に合わせて読んだ初期化子
+// 配列の要素や構造体のメンバごとに子を持つ
+struct Initializer {
+    ty: Type,
+    // スカラー、または構造体全体を初期化する式
+    expr: Option<Node>,
+    children: Vec<Initializer>,
+    // 共用体で初期化するメンバ
+    member: usize,
+}
+
+impl Initializer {
+    fn new(ty: Type) -> Initializer {
+        let children = match &ty {
+            TY_ARRAY { base, len } => (0..len.unwrap_or(0))
+                .map(|_| Initializer::new(*base.clone()))
+                .collect(),
+            TY_STRUCT(st) => st.members().into_iter().map(|m| Initializer::new(m.ty)).collect(),
+            _ => Vec::new(),
+        };
+        return Initializer {
+            ty,
+            expr: None,
+            children,
+            member: 0,
+        };
+    }
+
+    // 長さを省略した配列は、添字に合わせて要素を増やす
+    fn child(&mut self, i: usize) -> &mut Initializer {
+        if let TY_ARRAY { base, len: None } = &self.ty {
+            check_array_size(base, i as u64 + 1);
+            while self.children.len() <= i {
+                self.children.push(Initializer::new(*base.clone()));
+            }
+        }
+        if i >= self.children.len() {
+            eprintln!("初期化子の要素が多すぎます");
+            std::process::exit(1);
+        }
+        return &mut self.children[i];
+    }
+}
+
+// initializer = "{" initializer-list "}" | assign
+fn initializer(tokens: &mut VecDeque<Token>, ty: Type, env: &mut Env) -> Initializer {
+    let mut init = Initializer::new(ty);
+    if init.ty.is_struct() && !peek(tokens, "{") {
+        // 構造体は同じ型の値で初期化することもできる
+        let expr = assign(tokens, env);
+        if expr.ty() != init.ty {
+            eprintln!("初期化式の型が一致しません");
+            std::process::exit(1);
+        }
+        init.expr = Some(expr);
+        return init;
+    }
+    if init.ty.is_array() && !peek(tokens, "{") {
+        eprintln!("配列の初期化子は{{}}で囲む必要があります");
+        std::process::exit(1);
+    }
+    initializer2(tokens, &mut init, env);
+    // int a[] = {1, 2, 3}; の長さは初期化子の要素数で決まる
+    if let TY_ARRAY { base, len: None } = &init.ty {
+        init.ty = base.clone().array_of(init.children.len() as u32);
+    }
+    return init;
+}
+
+// 配列や構造体の初期化子の中括弧は省略できる
+// 省略したときは、続く値を要素やメンバに順に割り当てる
+fn initializer2(tokens: &mut VecDeque<Token>, init: &mut Initializer, env: &mut Env) {
+    if init.ty.is_array() {
+        if consume(tokens, "{") {
+            array_initializer(tokens, init, env);
+        } else {
+            elided_initializer(tokens, init, env);
+        }
+        return;
+    }
+    if init.ty.is_struct() {
+        if consume(tokens, "{") {
+            struct_initializer(tokens, init, env);
+        } else {
+            elided_initializer(tokens, init, env);
+        }
+        return;
+    }
+    // int x = {3}; のようにスカラーを中括弧で囲んでもよい
+    if consume(tokens, "{") {
+        initializer2(tokens, init, env);
+        if !consume_end(tokens) {
+            eprintln!("初期化子の要素が多すぎます");
+            std::process::exit(1);
+        }
+        return;
+    }
+    let expr = assign(tokens, env);
+    if expr.ty().is_struct() || expr.ty() == TY_VOID {
+        eprintln!("初期化式の型が一致しません");
+        std::process::exit(1);
+    }
+    init.expr = Some(expr);
+}
+
+// array-initializer = (designation? initializer ("," designation? initializer)*)? ","? "}"
+fn array_initializer(tokens: &mut VecDeque<Token>, init: &mut Initializer, env: &mut Env) {
+    let mut i = 0;
+    let mut first = true;
+    while !consume_end(tokens) {
+        if !first {
+            expect(tokens, ",");
+        }
+        first = false;
+        if consume(tokens, "[") {
+            i = array_designator(tokens, env);
+            designation(tokens, init.child(i), env);
+        } else {
+            initializer2(tokens, init.child(i), env);
+        }
+        i += 1;
+    }
+}
+
+// struct-initializer = (designation? initializer ("," designation? initializer)*)? ","? "}"
+//
+// 共用体はどれか1つのメンバだけを初期化する
+fn struct_initializer(tokens: &mut VecDeque<Token>, init: &mut Initializer, env: &mut Env) {
+    let is_union = matches!(&init.ty, TY_STRUCT(st) if st.is_union());
+    let mut i = 0;
+    let mut first = true;
+    while !consume_end(tokens) {
+        if !first {
+            expect(tokens, ",");
+        }
+        if consume(tokens, ".") {
+            i = member_designator(tokens, init, env);
+        } else {
+            if i >= init.children.len() || (is_union && !first) {
+                eprintln!("初期化子の要素が多すぎます");
+                std::process::exit(1);
+            }
+            init.member = i;
+            initializer2(tokens, &mut init.children[i], env);
+        }
+        first = false;
+        i += 1;
+    }
+}
+
+// 中括弧を省略した配列や構造体に、入るだけの値を割り当てる
+// 指示子が来たら外側の初期化子に任せる
+fn elided_initializer(tokens: &mut VecDeque<Token>, init: &mut Initializer, env: &mut Env) {
+    let n = match &init.ty {
+        TY_STRUCT(st) if st.is_union() => 1,
+        _ => init.children.len(),
+    };
+    for i in 0..n {
+        if is_end(tokens) {
+            return;
+        }
+        if i > 0 {
+            if tokens.get(1).is_some_and(|token| token.str == "[" || token.str == ".") {
+                return;
+            }
+            expect(tokens, ",");
+        }
+        initializer2(tokens, &mut init.children[i], env);
+    }
+}
+
+// designation = ("[" const-expr "]" | "." ident)* "=" initializer
+//
+// 先頭の指示子は呼び出し側で読んでおく
+fn designation(tokens: &mut VecDeque<Token>, init: &mut Initializer, env: &mut Env) {
+    if consume(tokens, "[") {
+        if !init.ty.is_array() {
+            eprintln!("配列ではない値に添字を指定しています");
+            std::process::exit(1);
+        }
+        let i = array_designator(tokens, env);
+        designation(tokens, init.child(i), env);
+        return;
+    }
+    if consume(tokens, ".") {
+        if !init.ty.is_struct() {
+            eprintln!("構造体ではない値にメンバを指定しています");
+            std::process::exit(1);
+        }
+        member_designator(tokens, init, env);
+        return;
+    }
+    expect(tokens, "=");
+    initializer2(tokens, init, env);
+}
+
+// "[" の後の const-expr "]"
+fn array_designator(tokens: &mut VecDeque<Token>, env: &mut Env) -> usize {
+    let i = const_expr(tokens, env);
+    if i < 0 {
+        eprintln!("配列の添字が負です");
+        std::process::exit(1);
+    }
+    expect(tokens, "]");
+    return i as usize;
+}
+
+// "." の後の ident と、それに続く指示子と初期化子
+//
+// 初期化したメンバの位置を返す
+fn member_designator(tokens: &mut VecDeque<Token>, init: &mut Initializer, env: &mut Env) -> usize {
+    let name = match consume_tk(tokens, TK_IDENT) {
+        Some(token) => token.str,
+        None => {
+            eprintln!("メンバ名を期待しましたが、ありませんでした");
+            std::process::exit(1);
+        }
+    };
+    return designate_member(tokens, init, &name, env);
+}
+
+// 名前のないメンバ(無名の構造体・共用体)の中のメンバは、その名前のないメンバを通して初期化する
+fn designate_member(
+    tokens: &mut VecDeque<Token>,
+    init: &mut Initializer,
+    name: &str,
+    env: &mut Env,
+) -> usize {
+    let members = match &init.ty {
+        TY_STRUCT(st) => st.members(),
+        _ => unreachable!(),
+    };
+    for (i, member) in members.iter().enumerate() {
+        if member.name == name {
+            init.member = i;
+            designation(tokens, &mut init.children[i], env);
+            return i;
+        }
+        if let (true, TY_STRUCT(inner)) = (member.name.is_empty(), &member.ty) {
+            if inner.find_member(name).is_some() {
+                init.member = i;
+                designate_member(tokens, &mut init.children[i], name, env);
+                return i;
+            }
+        }
+    }
+    eprintln!("{}というメンバはありません", name);
+    std::process::exit(1);
+}
+
+fn is_end(tokens: &VecDeque<Token>) -> bool {
+    return peek(tokens, "}")
+        || (peek(tokens, ",") && tokens.get(1).is_some_and(|token| token.str == "}"));
+}
+
+// 初期化子の終わりの "}" か ",}" を読む
+fn consume_end(tokens: &mut VecDeque<Token>) -> bool {
+    if is_end(tokens) {
+        consume(tokens, ",");
+        expect(tokens, "}");
+        return true;
+    }
+    return false;
+}
+
+// ローカル変数の初期化は、全体を0で埋めてから要素やメンバに代入する
+fn local_init(var: Node, init: &Initializer) -> Vec<Node> {
+    let mut stmts: Vec<Node> = Vec::new();
+    if init.expr.is_none() {
+        if let ND_LVAR { offset, ty, align } = &var {
+            stmts.push(ND_MEMZERO {
+                offset: *offset,
+                size: ty.size() + align.saturating_sub(16),
+            });
+        }
+    }
+    init_assignments(init, var, &mut stmts);
+    return stmts;
+}
+
+fn init_assignments(init: &Initializer, lhs: Node, stmts: &mut Vec<Node>) {
+    if let Some(expr) = &init.expr {
+        stmts.push(ND_EXPR_STMT {
+            expr: Box::new(ND_ASSIGN {
+                lhs: Box::new(lhs),
+                rhs: Box::new(new_cast(expr.clone(), init.ty.clone())),
+            }),
+        });
+        return;
+    }
+    match &init.ty {
+        TY_ARRAY { .. } => {
+            for (i, child) in init.children.iter().enumerate() {
+                let elem = ND_DEREF {
+                    expr: Box::new(new_add(lhs.clone(), new_num(i as i64))),
+                };
+                init_assignments(child, elem, stmts);
+            }
+        }
+        TY_STRUCT(st) => {
+            for (i, (member, child)) in st.members().into_iter().zip(&init.children).enumerate() {
+                if st.is_union() && i != init.member {
+                    continue;
+                }
+                let elem = ND_MEMBER {
+                    expr: Box::new(lhs.clone()),
+                    offset: member.offset,
+                    ty: member.ty,
+                };
+                init_assignments(child, elem, stmts);
+            }
+        }
+        _ => {}
+    }
+}
+
+// 初期化子の値をリトルエンディアンで並べる
+fn write_static_data(init: &Initializer, offset: usize, data: &mut StaticData) {
+    if let Some(expr) = &init.expr {
+        if init.ty.is_struct() {
+            eprintln!("グローバル変数の初期値は定数式でなければなりません");
+            std::process::exit(1);
+        }
+        let size = init.ty.size() as usize;
+        let mut label = None;
+        let val = eval_reloc(&new_cast(expr.clone(), init.ty.clone()), &mut label);
+        match label {
+            Some(label) if size == 8 => data.relocs.push(Reloc {
+                offset,
+                label,
+                addend: val,
+            }),
+            Some(_) => {
+                eprintln!("アドレスを8バイトより小さい型に入れることはできません");
+                std::process::exit(1);
+            }
+            None => data.bytes[offset..offset + size].copy_from_slice(&val.to_le_bytes()[..size]),
+        }
+        return;
+    }
+    match &init.ty {
+        TY_ARRAY { base, .. } => {
+            for (i, child) in init.children.iter().enumerate() {
+                write_static_data(child, offset + i * base.size() as usize, data);
+            }
+        }
+        TY_STRUCT(st) => {
+            for (i, (member, child)) in st.members().into_iter().zip(&init.children).enumerate() {
+                if st.is_union() && i != init.member {
+                    continue;
+                }
+                write_static_data(child, offset + member.offset as usize, data);
+            }
+        }
+        _ => {}
+    }
+}
+
+// typedef = declarator ("," declarator)* ";"
+fn parse_typedef(tokens: &mut VecDeque<Token>, base: Type, env: &mut Env) {
+    loop {
+        let (ty, token) = declarator(tokens, base.clone(), env);
+        env.declare(token.str, SYM_TYPEDEF { ty });
+        if !consume(tokens, ",") {
+            break;
+        }
+    }
+    expect(tokens, ";");
+}
+
+const TYPE_KEYWORDS: [&str; 15] = [
+    "typedef", "static", "extern", "_Alignas", "void", "_Bool", "char", "short", "int", "long",
+    "signed", "unsigned", "struct", "union", "enum",
+];
+
+// 型名は予約語か、typedefで宣言された名前
+// 同じ名前の変数が内側のスコープにあれば、そちらが優先される
+fn is_typename_token(token: &Token, env: &Env) -> bool {
+    match token.kind {
+        TK_RESERVED => return TYPE_KEYWORDS.contains(&token.str.as_str()),
+        TK_IDENT => return find_typedef(&token.str, env).is_some(),
+        _ => return false,
+    }
+}
+
+fn find_typedef(name: &str, env: &Env) -> Option<Type> {
+    match env.find_var(name) {
+        Some(SYM_TYPEDEF { ty }) => return Some(ty.clone()),
+        _ => return None,
+    }
+}
+
+fn is_typename(tokens: &VecDeque<Token>, env: &Env) -> bool {
+    return is_typename_at(tokens, 0, env);
+}
+
+// 先頭からn番目のトークンが型名か
+fn is_typename_at(tokens: &VecDeque<Token>, n: usize, env: &Env) -> bool {
+    if let Some(token) = tokens.get(n) {
+        return is_typename_token(token, env);
+    }
+    return false;
+}
+
+// declspec       = ("typedef" | "static" | "extern" | alignas | type-specifier)+
+// alignas        = "_Alignas" "(" (type-name | const-expr) ")"
+// type-specifier = "void" | "_Bool" | "char" | "short" | "int" | "long" | "signed" | "unsigned"
+//                | ("struct" | "union") struct-decl
+//                | "enum" enum-specifier
+//                | typedef-name
+//
+// 型指定子は順不同で書けるので、それぞれの出現回数から型を決める
+// void・_Bool・構造体・共用体・列挙型・typedef名は他の型指定子と組み合わせられない
+fn declspec(tokens: &mut VecDeque<Token>, env: &mut Env, mut attr: Option<&mut VarAttr>) -> Type {
+    let mut ty: Option<Type> = None;
+    let (mut char_, mut short, mut int, mut long, mut signed, mut unsigned) = (0, 0, 0, 0, 0, 0);
+    while is_typename(tokens, env) {
+        if consume(tokens, "_Alignas") {
+            let attr = match attr.as_deref_mut() {
+                Some(attr) => attr,
+                None => {
+                    eprintln!("ここでは_Alignasを使うことはできません");
+                    std::process::exit(1);
+                }
+            };
+            expect(tokens, "(");
+            let align = if is_typename(tokens, env) {
+                align_of(&type_name(tokens, env)) as i64
+            } else {
+                const_expr(tokens, env)
+            };
+            expect(tokens, ")");
+            if align <= 0 || align & (align - 1) != 0 {
+                eprintln!("アライメントは2のべき乗でなければなりません");
+                std::process::exit(1);
+            }
+            // 複数指定したときは一番厳しいものを使う
+            attr.align = attr.align.max(align as u32);
+            continue;
+        }
+        if ["typedef", "static", "extern"].iter().any(|kw| peek(tokens, kw)) {
+            let token = tokens.pop_front().unwrap();
+            match attr.as_deref_mut() {
+                Some(attr) => match token.str.as_str() {
+                    "typedef" => attr.is_typedef = true,
+                    "static" => attr.is_static = true,
+                    _ => attr.is_extern = true,
+                },
+                None => {
+                    eprintln!("ここでは{}を使うことはできません", token.str);
+                    std::process::exit(1);
+                }
+            }
+            continue;
+        }
+        let seen = ty.is_some() || char_ + short + int + long + signed + unsigned > 0;
+        if tokens[0].kind == TK_IDENT {
+            // int T; のTは、Tがtypedef名でも新しく宣言する名前として扱う
+            if seen {
+                break;
+            }
+            let token = tokens.pop_front().unwrap();
+            ty = find_typedef(&token.str, env);
+            continue;
+        }
+        if ["void", "_Bool", "struct", "union", "enum"].iter().any(|kw| peek(tokens, kw)) {
+            if seen {
+                eprintln!("不正な型の組み合わせです");
+                std::process::exit(1);
+            }
+            ty = Some(if consume(tokens, "void") {
+                TY_VOID
+            } else if consume(tokens, "_Bool") {
+                TY_BOOL
+            } else if consume(tokens, "struct") {
+                struct_decl(tokens, env, false)
+            } else if consume(tokens, "union") {
+                struct_decl(tokens, env, true)
+            } else {
+                expect(tokens, "enum");
+                enum_specifier(tokens, env)
+            });
+        } else if consume(tokens, "char") {
+            char_ += 1;
+        } else if consume(tokens, "short") {
+            short += 1;
+        } else if consume(tokens, "int") {
+            int += 1;
+        } else if consume(tokens, "long") {
+            long += 1;
+        } else if consume(tokens, "signed") {
+            signed += 1;
+        } else if consume(tokens, "unsigned") {
+            unsigned += 1;
+        }
+    }
+    if let Some(attr) = attr {
+        if attr.is_typedef as u8 + attr.is_static as u8 + attr.is_extern as u8 > 1 {
+            eprintln!("記憶域クラス指定子は1つしか指定できません");
+            std::process::exit(1);
+        }
+    }
+    if let Some(ty) = ty {
+        if char_ + short + int + long + signed + unsigned > 0 {
+            eprintln!("不正な型の組み合わせです");
+            std::process::exit(1);
+        }
+        return ty;
+    }
+    let valid = signed + unsigned <= 1
+        && char_ <= 1
+        && short <= 1
+        && int <= 1
+        && long <= 2
+        && (char_ == 0 || short + int + long == 0)
+        && (short == 0 || long == 0);
+    if !valid {
+        eprintln!("不正な型の組み合わせです");
+        std::process::exit(1);
+    }
+    return match (char_, short, long, unsigned) {
+        (1, _, _, 0) => TY_CHAR,
+        (1, _, _, _) => TY_UCHAR,
+        (_, 1, _, 0) => TY_SHORT,
+        (_, 1, _, _) => TY_USHORT,
+        // long long は long と同じ大きさ
+        (_, _, 0, 0) => TY_INT,
+        (_, _, 0, _) => TY_UINT,
+        (_, _, _, 0) => TY_LONG,
+        _ => TY_ULONG,
+    };
+}
+
+// struct-decl = ident? ("{" struct-member* "}")?
+//
+// 構造体と共用体はタグの名前空間を共有する
+fn struct_decl(tokens: &mut VecDeque<Token>, env: &mut Env, is_union: bool) -> Type {
+    let tag = consume_tk(tokens, TK_IDENT);
+    if !peek(tokens, "{") {
+        // 本体のない struct T は既存の定義を参照するか、不完全型を宣言する
+        let tag = match tag {
+            Some(tag) => tag,
+            None => {
+                eprintln!("構造体のタグか本体が必要です");
+                std::process::exit(1);
+            }
+        };
+        match env.find_tag(&tag.str) {
+            Some(TY_STRUCT(st)) if st.is_union() == is_union => return TY_STRUCT(st.clone()),
+            Some(_) => {
+                eprintln!("{}は別の種類のタグとして宣言されています", tag.str);
+                std::process::exit(1);
+            }
+            None => {}
+        }
+        let ty = TY_STRUCT(StructRef::incomplete(is_union));
+        env.declare_tag(tag.str, ty.clone());
+        return ty;
+    }
+    expect(tokens, "{");
+    // メンバから自分自身を参照できるように、先にタグを登録しておく
+    let st = match &tag {
+        Some(tag) => match env.find_tag_in_current_scope(&tag.str) {
+            Some(TY_STRUCT(st)) if !st.is_complete() && st.is_union() == is_union => st.clone(),
+            Some(_) => {
+                eprintln!("構造体{}はすでに定義されています", tag.str);
+                std::process::exit(1);
+            }
+            None => {
+                let st = StructRef::incomplete(is_union);
+                env.declare_tag(tag.str.clone(), TY_STRUCT(st.clone()));
+                st
+            }
+        },
+        None => StructRef::incomplete(is_union),
+    };
+    let members = struct_members(tokens, env);
+    st.set_members(members);
+    return TY_STRUCT(st);
+}
+
+// enum-specifier = ident? "{" enumerator ("," enumerator)* ","? "}"
+//                | ident
+// enumerator     = ident ("=" const-expr)?
+//
+// 列挙型はintとして扱い、列挙定数は宣言されたスコープで整数定数になる
+fn enum_specifier(tokens: &mut VecDeque<Token>, env: &mut Env) -> Type {
+    let tag = consume_tk(tokens, TK_IDENT);
+    if !consume(tokens, "{") {
+        let tag = match tag {
+            Some(tag) => tag,
+            None => {
+                eprintln!("列挙型のタグか本体が必要です");
+                std::process::exit(1);
+            }
+        };
+        match env.find_tag(&tag.str) {
+            Some(TY_INT) => return TY_INT,
+            Some(_) => {
+                eprintln!("{}は別の種類のタグとして宣言されています", tag.str);
+                std::process::exit(1);
+            }
+            None => {
+                eprintln!("列挙型{}は定義されていません", tag.str);
+                std::process::exit(1);
+            }
+        }
+    }
+    // 値を省略した列挙定数は、直前の値に1を足した値になる
+    // 列挙定数の値はintの範囲に収まらなければならない
+    let mut next = 0;
+    loop {
+        let name = match consume_tk(tokens, TK_IDENT) {
+            Some(token) => token.str,
+            None => {
+                eprintln!("列挙定数の名前を期待しましたが、ありませんでした");
+                std::process::exit(1);
+            }
+        };
+        let val = if consume(tokens, "=") {
+            const_expr(tokens, env)
+        } else {
+            next
+        };
+        if !(i32::MIN as i64..=i32::MAX as i64).contains(&val) {
+            eprintln!("列挙定数{}の値がintの範囲を超えています", name);
+            std::process::exit(1);
+        }
+        env.declare(name, SYM_ENUM_CONST { val });
+        next = val + 1;
+        if consume(tokens, "}") {
+            break;
+        }
+        expect(tokens, ",");
+        if consume(tokens, "}") {
+            break;
+        }
+    }
+    if let Some(tag) = tag {
+        if env.find_tag_in_current_scope(&tag.str).is_some() {
+            eprintln!("{}はすでに定義されています", tag.str);
+            std::process::exit(1);
+        }
+        env.declare_tag(tag.str, TY_INT);
+    }
+    return TY_INT;
+}
+
+// struct-member = declspec declarator ("," declarator)* ";"
+//               | ("struct" | "union") "{" struct-member* "}" ";"
+fn struct_members(tokens: &mut VecDeque<Token>, env: &mut Env) -> Vec<(String, Type, u32)> {
+    let mut members: Vec<(String, Type, u32)> = Vec::new();
+    // 重複を調べるために、名前のないメンバの中のメンバも並べておく
+    let mut names: Vec<String> = Vec::new();
+    while !consume(tokens, "}") {
+        let anonymous = (peek(tokens, "struct") || peek(tokens, "union"))
+            && tokens.get(1).is_some_and(|token| token.str == "{");
+        let mut attr = VarAttr::default();
+        let base = declspec(tokens, env, Some(&mut attr));
+        if attr.is_typedef || attr.is_static || attr.is_extern {
+            eprintln!("メンバに記憶域クラス指定子を付けることはできません");
+            std::process::exit(1);
+        }
+        // 名前のないメンバのメンバは、外側の構造体のメンバとして参照できる
+        if anonymous && consume(tokens, ";") {
+            if let TY_STRUCT(st) = &base {
+                for name in st.member_names() {
+                    if names.contains(&name) {
+                        eprintln!("メンバ{}が重複しています", name);
+                        std::process::exit(1);
+                    }
+                    names.push(name);
+                }
+            }
+            let align = attr.align_of("名前のないメンバ", &base);
+            members.push((String::new(), base, align));
+            continue;
+        }
+        loop {
+            let (ty, token) = declarator(tokens, base.clone(), env);
+            if ty.is_incomplete() {
+                eprintln!("メンバ{}の型は不完全です", token.str);
+                std::process::exit(1);
+            }
+            if names.contains(&token.str) {
+                eprintln!("メンバ{}が重複しています", token.str);
+                std::process::exit(1);
+            }
+            names.push(token.str.clone());
+            let align = attr.align_of(&token.str, &ty);
+            members.push((token.str, ty, align));
+            if !consume(tokens, ",") {
+                break;
+            }
+        }
+        expect(tokens, ";");
+    }
+    return members;
+}
+
+// type-name = declspec abstract-declarator
+fn type_name(tokens: &mut VecDeque<Token>, env: &mut Env) -> Type {
+    let base = declspec(tokens, env, None);
+    let (ty, name) = declarator_opt(tokens, base, env);
+    if let Some(name) = name {
+        eprintln!("型名の中に名前{}を書くことはできません", name.str);
+        std::process::exit(1);
+    }
+    return ty;
+}
+
+fn declarator(tokens: &mut VecDeque<Token>, base: Type, env: &mut Env) -> (Type, Token) {
+    match declarator_opt(tokens, base, env) {
+        (ty, Some(token)) => return (ty, token),
+        (_, None) => {
+            eprintln!("変数名を期待しましたが、ありませんでした");
+            std::process::exit(1);
+        }
+    }
+}
+
+// declarator = "*"* ("(" declarator ")" | ident?) type-suffix
+//
+// 名前を省略すると抽象宣言子になる
+// int (*fp)(int) のような括弧の中の宣言子は、括弧の外の型が決まってから読む
+fn declarator_opt(tokens: &mut VecDeque<Token>, base: Type, env: &mut Env) -> (Type, Option<Token>) {
+    let mut ty = base;
+    while consume(tokens, "*") {
+        ty = ty.pointer_to();
+    }
+    if peek(tokens, "(") && is_nested_declarator(tokens, env) {
+        let close = matching_paren(tokens);
+        let mut inner: VecDeque<Token> = tokens.drain(..=close).collect();
+        inner.pop_front();
+        inner.pop_back();
+        let outer = type_suffix(tokens, ty, env);
+        let (ty, name) = declarator_opt(&mut inner, outer, env);
+        if let Some(token) = inner.front() {
+            eprintln!("宣言子の中の{}を読み込めませんでした", token.str);
+            std::process::exit(1);
+        }
+        return (ty, name);
+    }
+    let name = consume_tk(tokens, TK_IDENT);
+    return (type_suffix(tokens, ty, env), name);
+}
+
+// 宣言子の中の "(" が入れ子の宣言子の始まりか、関数の引数リストの始まりか
+fn is_nested_declarator(tokens: &VecDeque<Token>, env: &Env) -> bool {
+    match tokens.get(1) {
+        Some(token) if token.kind == TK_IDENT => return !is_typename_token(token, env),
+        Some(token) => return token.str == "*" || token.str == "(" || token.str == "[",
+        None => return false,
+    }
+}
+
+// 先頭の "(" に対応する ")" の位置
+fn matching_paren(tokens: &VecDeque<Token>) -> usize {
+    let mut depth = 0;
+    for (i, token) in tokens.iter().enumerate() {
+        if token.kind != TK_RESERVED {
+            continue;
+        }
+        if token.str == "(" {
+            depth += 1;
+        } else if token.str == ")" {
+            depth -= 1;
+            if depth == 0 {
+                return i;
+            }
+        }
+    }
+    eprintln!("対応する)がありません");
+    std::process::exit(1);
+}
+
+// type-suffix = "(" func-params
+//             | "[" const-expr? "]" type-suffix
+//             | ε
+fn type_suffix(tokens: &mut VecDeque<Token>, base: Type, env: &mut Env) -> Type {
+    if consume(tokens, "(") {
+        let params = func_params(tokens, env);
+        if base.is_array() || base.is_func() {
+            eprintln!("配列や関数を返す関数は宣言できません");
+            std::process::exit(1);
+        }
+        // 構造体の受け渡しの呼び出し規約には対応していない
+        if base.is_struct() {
+            eprintln!("構造体を返す関数は宣言できません");
+            std::process::exit(1);
+        }
+        return base.func_returning(params);
+    }
+    if consume(tokens, "[") {
+        let len = if consume(tokens, "]") {
+            None
+        } else {
+            let len = const_expr(tokens, env);
+            if len < 0 {
+                eprintln!("配列の長さが負です");
+                std::process::exit(1);
+            }
+            expect(tokens, "]");
+            Some(len as u64)
+        };
+        // int m[3][4] は「int 4つの配列」3つの配列
+        let ty = type_suffix(tokens, base, env);
+        if ty.is_array() && ty.is_incomplete() {
+            eprintln!("長さを省略できるのは一番外側の配列だけです");
+            std::process::exit(1);
+        }
+        return match len {
+            Some(len) => {
+                check_array_size(&ty, len);
+                ty.array_of(len as u32)
+            }
+            None => ty.array_of_unknown_len(),
+        };
+    }
+    return base;
+}
+
+// 型の大きさは u32 で表すので、それに収まらない配列は作れない
+fn check_array_size(base: &Type, len: u64) {
+    if base.size() as u64 * len > u32::MAX as u64 {
+        eprintln!("配列が大きすぎます");
+        std::process::exit(1);
+    }
+}
+
+// func-params = ("void" | param ("," param)*)? ")"
+// param       = declspec declarator
+//
+// f() は引数を指定しない宣言なので None を返す
+// 引数名は env.param_names に残しておく
+fn func_params(tokens: &mut VecDeque<Token>, env: &mut Env) -> Option<Vec<Type>> {
+    if consume(tokens, ")") {
+        env.param_names = Vec::new();
+        return None;
+    }
+    let mut params: Vec<Type> = Vec::new();
+    if peek(tokens, "void") && tokens.get(1).is_some_and(|token| token.str == ")") {
+        tokens.drain(..2);
+        env.param_names = Vec::new();
+        return Some(params);
+    }
+    let mut names: Vec<Option<Token>> = Vec::new();
+    loop {
+        let base = declspec(tokens, env, None);
+        let (ty, name) = declarator_opt(tokens, base, env);
+        if ty.is_incomplete() && !ty.is_array() {
+            eprintln!("引数の型が不完全です");
+            std::process::exit(1);
+        }
+        if ty.is_struct() {
+            eprintln!("構造体を引数に取る関数は宣言できません");
+            std::process::exit(1);
+        }
+        // 配列や関数の引数はポインタとして受け取る
+        params.push(ty.decay());
+        names.push(name);
+        if !consume(tokens, ",") {
+            break;
+        }
+    }
+    expect(tokens, ")");
+    env.param_names = names;
+    return Some(params);
+}
+
+// stmt = expr? ";"
+//   | "{" compound-stmt
+//   | "if" "(" expr ")" stmt ("else" stmt)?
+//   | "while" "(" expr ")" stmt
+//   | "do" stmt "while" "(" expr ")" ";"
+//   | "for" "(" (declaration | expr? ";") expr? ";" expr? ")" stmt
+//   | "switch" "(" expr ")" stmt
+//   | "case" const-expr ":" stmt
+//   | "default" ":" stmt
+//   | "goto" ident ";"
+//   | ident ":" stmt
+//   | "break" ";"
+//   | "continue" ";"
+//   | "return" expr ";"
+fn stmt(tokens: &mut VecDeque<Token>, env: &mut Env) -> Node {
+    let node = if let Some(_token) = consume_tk(tokens, TK_RETURN) {
+        // voidを返す関数では値を返さない
+        let ret = if env.ret_ty == TY_VOID {
+            if !consume(tokens, ";") {
+                eprintln!("voidを返す関数で値を返すことはできません");
+                std::process::exit(1);
+            }
+            new_num(0)
+        } else {
+            let expr_node = expr(tokens, env);
+            expect(tokens, ";");
+            if expr_node.ty().is_struct() {
+                eprintln!("構造体の値を返すことはできません");
+                std::process::exit(1);
+            }
+            if expr_node.ty() == TY_VOID {
+                eprintln!("void型の値を返すことはできません");
+                std::process::exit(1);
+            }
+            new_cast(expr_node, env.ret_ty.clone())
+        };
+        ND_RETURN { ret: Box::new(ret) }
+    } else if let Some(_token) = consume_tk(tokens, TK_IF) {
+        expect(tokens, "(");
+        let cond = Box::new(scalar(expr(tokens, env)));
+        expect(tokens, ")");
+        let cons = Box::new(stmt(tokens, env));
+        let alt = Box::new(if consume(tokens, "else") {
+            stmt(tokens, env)
+        } else {
+            ND_NOTHING
+        });
+        ND_IF { cond, cons, alt }
+    } else if let Some(_token) = consume_tk(tokens, TK_WHILE) {
+        expect(tokens, "(");
+        let cond = Box::new(scalar(expr(tokens, env)));
+        expect(tokens, ")");
+        let id = env.new_label();
+        let body = Box::new(loop_body(tokens, id, env));
+        ND_WHILE { cond, body, id }
+    } else if consume(tokens, "do") {
+        let id = env.new_label();
+        let body = Box::new(loop_body(tokens, id, env));
+        if consume_tk(tokens, TK_WHILE).is_none() {
+            eprintln!("doの本体の後にwhileが必要です");
+            std::process::exit(1);
+        }
+        expect(tokens, "(");
+        let cond = Box::new(scalar(expr(tokens, env)));
+        expect(tokens, ")");
+        expect(tokens, ";");
+        ND_DO { body, cond, id }
+    } else if consume(tokens, ";") {
+        ND_NOTHING
+    } else if consume(tokens, "{") {
+        env.enter_scope();
+        let stmts = compound_stmt(tokens, env);
+        env.leave_scope();
+        ND_BLOCK { stmts }
+    } else if let Some(_token) = consume_tk(tokens, TK_FOR) {
+        // 初期化で宣言した変数のスコープはループの中だけ
+        env.enter_scope();
+        expect(tokens, "(");
+        let init = Box::new(if is_typename(tokens, env) {
+            declaration(tokens, env)
+        } else if consume(tokens, ";") {
+            ND_NOTHING
+        } else {
+            let nd = expr(tokens, env);
+            expect(tokens, ";");
+            ND_EXPR_STMT { expr: Box::new(nd) }
+        });
+        // 条件を省略すると無限ループになる
+        let cond = Box::new(if consume(tokens, ";") {
+            new_num(1)
+        } else {
+            let nd = scalar(expr(tokens, env));
+            expect(tokens, ";");
+            nd
+        });
+        let inc = Box::new(if consume(tokens, ")") {
+            ND_NOTHING
+        } else {
+            let nd = expr(tokens, env);
+            expect(tokens, ")");
+            ND_EXPR_STMT { expr: Box::new(nd) }
+        });
+        let id = env.new_label();
+        let body = Box::new(loop_body(tokens, id, env));
+        env.leave_scope();
+        ND_FOR {
+            init,
+            cond,
+            inc,
+            body,
+            id,
+        }
+    } else if consume(tokens, "switch") {
+        expect(tokens, "(");
+        let cond = int_promote(expr(tokens, env));
+        expect(tokens, ")");
+        let id = env.new_label();
+        let outer = env.switch.replace(SwitchCases {
+            cond_ty: cond.ty(),
+            cases: Vec::new(),
+            default: None,
+        });
+        // switch 文の中の break は switch 文を抜ける
+        let brk = env.brk.replace(id);
+        let body = Box::new(stmt(tokens, env));
+        env.brk = brk;
+        let sw = std::mem::replace(&mut env.switch, outer).unwrap();
+        ND_SWITCH {
+            cond: Box::new(cond),
+            body,
+            cases: sw.cases,
+            default: sw.default,
+            id,
+        }
+    } else if consume(tokens, "case") {
+        let val = const_expr(tokens, env);
+        expect(tokens, ":");
+        let label = env.new_label();
+        let sw = match env.switch.as_mut() {
+            Some(sw) => sw,
+            None => {
+                eprintln!("caseはswitch文の外では使えません");
+                std::process::exit(1);
+            }
+        };
+        // case の値は switch の式の型に変換して比べる
+        let val = eval(&new_cast(new_num(val), sw.cond_ty.clone()));
+        if sw.cases.iter().any(|(v, _)| *v == val) {
+            eprintln!("case {}が重複しています", val);
+            std::process::exit(1);
+        }
+        sw.cases.push((val, label));
+        ND_CASE {
+            label,
+            stmt: Box::new(stmt(tokens, env)),
+        }
+    } else if consume(tokens, "default") {
+        expect(tokens, ":");
+        let label = env.new_label();
+        let sw = match env.switch.as_mut() {
+            Some(sw) => sw,
+            None => {
+                eprintln!("defaultはswitch文の外では使えません");
+                std::process::exit(1);
+            }
+        };
+        if sw.default.is_some() {
+            eprintln!("defaultが重複しています");
+            std::process::exit(1);
+        }
+        sw.default = Some(label);
+        ND_CASE {
+            label,
+            stmt: Box::new(stmt(tokens, env)),
+        }
+    } else if consume(tokens, "goto") {
+        let name = match consume_tk(tokens, TK_IDENT) {
+            Some(token) => token.str,
+            None => {
+                eprintln!("gotoの後にラベル名が必要です");
+                std::process::exit(1);
+            }
+        };
+        expect(tokens, ";");
+        env.gotos.push(name.clone());
+        ND_GOTO { name }
+    } else if tokens.len() > 1 && tokens[0].kind == TK_IDENT && tokens[1].str == ":" {
+        let name = tokens.pop_front().unwrap().str;
+        expect(tokens, ":");
+        env.declare_label(name.clone());
+        ND_LABEL {
+            name,
+            stmt: Box::new(stmt(tokens, env)),
+        }
+    } else if consume(tokens, "break") {
+        expect(tokens, ";");
+        match env.brk {
+            Some(id) => ND_BREAK { id },
+            None => {
+                eprintln!("breakはループの外では使えません");
+                std::process::exit(1);
+            }
+        }
+    } else if consume(tokens, "continue") {
+        expect(tokens, ";");
+        match env.cont {
+            Some(id) => ND_CONTINUE { id },
+            None => {
+                eprintln!("continueはループの外では使えません");
+                std::process::exit(1);
+            }
+        }
+    } else {
+        let nd = expr(tokens, env);
+        expect(tokens, ";");
+        ND_EXPR_STMT { expr: Box::new(nd) }
+    };
+    return node;
+}
+
+// ループの本体の中では、break と continue の飛び先をそのループにする
+fn loop_body(tokens: &mut VecDeque<Token>, id: u32, env: &mut Env) -> Node {
+    let (brk, cont) = (env.brk, env.cont);
+    env.brk = Some(id);
+    env.cont = Some(id);
+    let body = stmt(tokens, env);
+    env.brk = brk;
+    env.cont = cont;
+    return body;
+}
+
+// expr = assign ("," expr)?
+fn expr(tokens: &mut VecDeque<Token>, env: &mut Env) -> Node {
+    let node = assign(tokens, env);
+    if consume(tokens, ",") {
+        return ND_COMMA {
+            lhs: Box::new(node),
+            rhs: Box::new(expr(tokens, env)),
+        };
+    }
+    return node;
+}
+
+// assign    = conditional (assign-op assign)?
+// assign-op = "=" | "+=" | "-=" | "*=" | "/=" | "%=" | "&=" | "|=" | "^=" | "<<=" | ">>="
+fn assign(tokens: &mut VecDeque<Token>, env: &mut Env) -> Node {
+    let mut node: Node = conditional(tokens, env);
+    for op in ["+", "-", "*", "/", "%", "&", "|", "^", "<<", ">>"] {
+        if consume(tokens, &format!("{}=", op)) {
+            let rhs = assign(tokens, env);
+            return new_op_assign(node, rhs, op, env);
+        }
+    }
+    if consume(tokens, "=") {
+        if node.ty().is_array() {
+            eprintln!("配列に代入することはできません");
+            std::process::exit(1);
+        }
+        let rhs = assign(tokens, env);
+        if node.ty() == TY_VOID || rhs.ty() == TY_VOID {
+            eprintln!("void型の値は代入できません");
+            std::process::exit(1);
+        }
+        // 構造体は同じ型の構造体としか代入できない
+        if (node.ty().is_struct() || rhs.ty().is_struct()) && node.ty() != rhs.ty() {
+            eprintln!("構造体と異なる型の値を代入することはできません");
+            std::process::exit(1);
+        }
+        // 右辺は左辺の型に変換してから代入する
+        let rhs = new_cast(rhs, node.ty());
+        node = ND_ASSIGN {
+            lhs: Box::new(node),
+            rhs: Box::new(rhs),
+        }
+    }
+    return node;
+}
+
+// a op= b は tmp = &a, *tmp = *tmp op b として、a を一度だけ評価する
+fn new_op_assign(lhs: Node, rhs: Node, op: &str, env: &mut Env) -> Node {
+    let ty = lhs.ty();
+    if ty.is_array() {
+        eprintln!("配列に代入することはできません");
+        std::process::exit(1);
+    }
+    let tmp = env.new_temp(ty.clone().pointer_to());
+    let addr = ND_ASSIGN {
+        lhs: Box::new(tmp.clone()),
+        rhs: Box::new(ND_ADDR {
+            expr: Box::new(lhs),
+        }),
+    };
+    let deref = || ND_DEREF {
+        expr: Box::new(tmp.clone()),
+    };
+    let val = new_binary(op, deref(), rhs);
+    return ND_COMMA {
+        lhs: Box::new(addr),
+        rhs: Box::new(ND_ASSIGN {
+            lhs: Box::new(deref()),
+            rhs: Box::new(new_cast(val, ty)),
+        }),
+    };
+}
+
+// 二項演算のノードを作る
+// 足し算と引き算以外は、ポインタを被演算子に取れない
+fn new_binary(op: &str, lhs: Node, rhs: Node) -> Node {
+    match op {
+        "+" => return new_add(lhs, rhs),
+        "-" => return new_sub(lhs, rhs),
+        "<<" => {
+            return ND_SHL {
+                lhs: Box::new(int_promote(lhs)),
+                rhs: Box::new(int_promote(rhs)),
             }
-        } else if consume(tokens, "<=") {
-            node = ND_LE {
-                lhs: Box::new(node),
-                rhs: Box::new(add(tokens, lvars)),
+        }
+        ">>" => {
+            return ND_SHR {
+                lhs: Box::new(int_promote(lhs)),
+                rhs: Box::new(int_promote(rhs)),
             }
-        } else if consume(tokens, ">") {
-            node = ND_LT {
-                lhs: Box::new(add(tokens, lvars)),
-                rhs: Box::new(node),
+        }
+        _ => {}
+    }
+    if !lhs.ty().is_integer() || !rhs.ty().is_integer() {
+        eprintln!("整数型の値が必要です");
+        std::process::exit(1);
+    }
+    match op {
+        "*" => {
+            let (lhs, rhs) = usual_arith_conv(lhs, rhs);
+            return ND_MUL { lhs, rhs };
+        }
+        "/" => {
+            let (lhs, rhs) = usual_arith_conv(lhs, rhs);
+            return ND_DIV { lhs, rhs };
+        }
+        "%" => {
+            let (lhs, rhs) = int_arith_conv(lhs, rhs);
+            return ND_MOD { lhs, rhs };
+        }
+        "&" => {
+            let (lhs, rhs) = int_arith_conv(lhs, rhs);
+            return ND_BITAND { lhs, rhs };
+        }
+        "|" => {
+            let (lhs, rhs) = int_arith_conv(lhs, rhs);
+            return ND_BITOR { lhs, rhs };
+        }
+        "^" => {
+            let (lhs, rhs) = int_arith_conv(lhs, rhs);
+            return ND_BITXOR { lhs, rhs };
+        }
+        _ => unreachable!(),
+    }
+}
+
+// conditional = logor ("?" expr ":" conditional)?
+fn conditional(tokens: &mut VecDeque<Token>, env: &mut Env) -> Node {
+    let cond = logor(tokens, env);
+    if !consume(tokens, "?") {
+        return cond;
+    }
+    let then = expr(tokens, env);
+    expect(tokens, ":");
+    let els = conditional(tokens, env);
+    return new_cond(scalar(cond), then, els);
+}
+
+// 2つの選択肢の型を揃えて、式全体の型を決める
+fn new_cond(cond: Node, then: Node, els: Node) -> Node {
+    let (tty, ety) = (then.ty().decay(), els.ty().decay());
+    let ty = if tty.is_integer() && ety.is_integer() {
+        common_type(&tty, &ety)
+    } else if tty == ety && (tty == TY_VOID || tty.is_struct()) {
+        tty
+    } else if let (Some(tbase), Some(ebase)) = (tty.base(), ety.base()) {
+        if tbase == ebase {
+            tty
+        } else if *tbase == TY_VOID || *ebase == TY_VOID {
+            TY_VOID.pointer_to()
+        } else if let Some(ty) = composite_type(tbase, ebase) {
+            ty.pointer_to()
+        } else {
+            eprintln!("条件演算子の2つの選択肢のポインタの型が一致しません");
+            std::process::exit(1);
+        }
+    } else if tty.base().is_some() && matches!(els, ND_NUM { val: 0, .. }) {
+        tty
+    } else if ety.base().is_some() && matches!(then, ND_NUM { val: 0, .. }) {
+        ety
+    } else {
+        eprintln!("条件演算子の2つの選択肢の型が一致しません");
+        std::process::exit(1);
+    };
+    // void の値は使われないので変換しない
+    let (then, els) = if ty == TY_VOID {
+        (then, els)
+    } else {
+        (new_cast(then, ty.clone()), new_cast(els, ty.clone()))
+    };
+    return ND_COND {
+        cond: Box::new(cond),
+        then: Box::new(then),
+        els: Box::new(els),
+        ty,
+    };
+}
+
+// const-expr = conditional
+//
+// コンパイル時に値を決める
+fn const_expr(tokens: &mut VecDeque<Token>, env: &mut Env) -> i64 {
+    let node = conditional(tokens, env);
+    return eval(&node);
+}
+
+// グローバル変数の初期値は、ほかのグローバル変数のアドレスに定数を足したものでもよい
+// そのときは label にアドレスの元になる名前を入れる
+fn eval_reloc(node: &Node, label: &mut Option<String>) -> i64 {
+    match node {
+        ND_ADD { lhs, rhs } => eval_reloc(lhs, label).wrapping_add(eval(rhs)),
+        ND_SUB { lhs, rhs } if rhs.ty().is_integer() => {
+            eval_reloc(lhs, label).wrapping_sub(eval(rhs))
+        }
+        ND_CAST { expr, ty } if ty.size() == 8 => eval_reloc(expr, label),
+        ND_ADDR { expr } => eval_addr(expr, label),
+        // 配列と関数は先頭のアドレスを値とする
+        ND_GVAR { ty, .. } | ND_FUNC { ty, .. } if ty.is_array() || ty.is_func() => {
+            eval_addr(node, label)
+        }
+        _ => eval(node),
+    }
+}
+
+fn eval_addr(node: &Node, label: &mut Option<String>) -> i64 {
+    match node {
+        ND_GVAR { name, .. } | ND_FUNC { name, .. } => {
+            *label = Some(name.clone());
+            return 0;
+        }
+        ND_DEREF { expr } => eval_reloc(expr, label),
+        ND_MEMBER { expr, offset, .. } => eval_addr(expr, label) + *offset as i64,
+        _ => {
+            eprintln!("定数式ではありません");
+            std::process::exit(1);
+        }
+    }
+}
+
+// 計算した値は、実行時と同じように式の型に収まるように切り詰める
+fn eval(node: &Node) -> i64 {
+    let val = eval2(node);
+    let ty = node.ty();
+    if ty == TY_BOOL {
+        return (val != 0) as i64;
+    }
+    return match (ty.size(), ty.is_unsigned()) {
+        (1, false) => val as i8 as i64,
+        (1, true) => val as u8 as i64,
+        (2, false) => val as i16 as i64,
+        (2, true) => val as u16 as i64,
+        (4, false) => val as i32 as i64,
+        (4, true) => val as u32 as i64,
+        _ => val,
+    };
+}
+
+fn eval2(node: &Node) -> i64 {
+    match node {
+        ND_NUM { val, .. } => *val,
+        ND_CAST { expr, .. } => eval(expr),
+        ND_ADD { lhs, rhs } => eval(lhs).wrapping_add(eval(rhs)),
+        ND_SUB { lhs, rhs } => eval(lhs).wrapping_sub(eval(rhs)),
+        ND_MUL { lhs, rhs } => eval(lhs).wrapping_mul(eval(rhs)),
+        ND_DIV { lhs, rhs } => {
+            let (l, r) = (eval(lhs), eval(rhs));
+            if r == 0 {
+                eprintln!("定数式の中で0で割っています");
+                std::process::exit(1);
             }
-        } else if consume(tokens, ">=") {
-            node = ND_LE {
-                lhs: Box::new(add(tokens, lvars)),
-                rhs: Box::new(node),
+            if lhs.ty().is_unsigned() {
+                return ((l as u64) / (r as u64)) as i64;
+            }
+            return l.wrapping_div(r);
+        }
+        ND_EQ { lhs, rhs } => (eval(lhs) == eval(rhs)) as i64,
+        ND_NE { lhs, rhs } => (eval(lhs) != eval(rhs)) as i64,
+        ND_LT { lhs, rhs } if lhs.ty().is_unsigned() => {
+            ((eval(lhs) as u64) < (eval(rhs) as u64)) as i64
+        }
+        ND_LT { lhs, rhs } => (eval(lhs) < eval(rhs)) as i64,
+        ND_LE { lhs, rhs } if lhs.ty().is_unsigned() => {
+            ((eval(lhs) as u64) <= (eval(rhs) as u64)) as i64
+        }
+        ND_LE { lhs, rhs } => (eval(lhs) <= eval(rhs)) as i64,
+        ND_MOD { lhs, rhs } => {
+            let (l, r) = (eval(lhs), eval(rhs));
+            if r == 0 {
+                eprintln!("定数式の中で0で割っています");
+                std::process::exit(1);
+            }
+            if lhs.ty().is_unsigned() {
+                return ((l as u64) % (r as u64)) as i64;
+            }
+            return l.wrapping_rem(r);
+        }
+        ND_BITAND { lhs, rhs } => eval(lhs) & eval(rhs),
+        ND_BITOR { lhs, rhs } => eval(lhs) | eval(rhs),
+        ND_BITXOR { lhs, rhs } => eval(lhs) ^ eval(rhs),
+        ND_BITNOT { expr } => !eval(expr),
+        ND_SHL { lhs, rhs } => eval(lhs).wrapping_shl(eval(rhs) as u32),
+        ND_SHR { lhs, rhs } if lhs.ty().is_unsigned() => {
+            (eval(lhs) as u64).wrapping_shr(eval(rhs) as u32) as i64
+        }
+        ND_SHR { lhs, rhs } => eval(lhs).wrapping_shr(eval(rhs) as u32),
+        ND_NOT { expr } => (eval(expr) == 0) as i64,
+        // 右辺は左辺で結果が決まらないときだけ評価する
+        ND_LOGAND { lhs, rhs } => (eval(lhs) != 0 && eval(rhs) != 0) as i64,
+        ND_LOGOR { lhs, rhs } => (eval(lhs) != 0 || eval(rhs) != 0) as i64,
+        ND_COND {
+            cond, then, els, ..
+        } => {
+            if eval(cond) != 0 {
+                eval(then)
+            } else {
+                eval(els)
             }
+        }
+        _ => {
+            eprintln!("定数式ではありません");
+            std::process::exit(1);
+        }
+    }
+}
+
+// logor      = logand ("||" logand)*
+fn logor(tokens: &mut VecDeque<Token>, env: &mut Env) -> Node {
+    let mut node: Node = logand(tokens, env);
+    while consume(tokens, "||") {
+        let rhs = logand(tokens, env);
+        node = ND_LOGOR {
+            lhs: Box::new(scalar(node)),
+            rhs: Box::new(scalar(rhs)),
+        };
+    }
+    return node;
+}
+
+// logand     = bitor ("&&" bitor)*
+fn logand(tokens: &mut VecDeque<Token>, env: &mut Env) -> Node {
+    let mut node: Node = bitor(tokens, env);
+    while consume(tokens, "&&") {
+        let rhs = bitor(tokens, env);
+        node = ND_LOGAND {
+            lhs: Box::new(scalar(node)),
+            rhs: Box::new(scalar(rhs)),
+        };
+    }
+    return node;
+}
+
+// bitor      = bitxor ("|" bitxor)*
+fn bitor(tokens: &mut VecDeque<Token>, env: &mut Env) -> Node {
+    let mut node: Node = bitxor(tokens, env);
+    while consume(tokens, "|") {
+        let (lhs, rhs) = int_arith_conv(node, bitxor(tokens, env));
+        node = ND_BITOR { lhs, rhs };
+    }
+    return node;
+}
+
+// bitxor     = bitand ("^" bitand)*
+fn bitxor(tokens: &mut VecDeque<Token>, env: &mut Env) -> Node {
+    let mut node: Node = bitand(tokens, env);
+    while consume(tokens, "^") {
+        let (lhs, rhs) = int_arith_conv(node, bitand(tokens, env));
+        node = ND_BITXOR { lhs, rhs };
+    }
+    return node;
+}
+
+// bitand     = equality ("&" equality)*
+fn bitand(tokens: &mut VecDeque<Token>, env: &mut Env) -> Node {
+    let mut node: Node = equality(tokens, env);
+    while consume(tokens, "&") {
+        let (lhs, rhs) = int_arith_conv(node, equality(tokens, env));
+        node = ND_BITAND { lhs, rhs };
+    }
+    return node;
+}
+
+// 真偽値として使う式はスカラー型でなければならない
+fn scalar(node: Node) -> Node {
+    if !node.ty().decay().is_scalar() {
+        eprintln!("スカラー型の値が必要です");
+        std::process::exit(1);
+    }
+    return node;
+}
+
+// equality   = relational ( "==" relational | "!=" relational)*
+fn equality(tokens: &mut VecDeque<Token>, env: &mut Env) -> Node {
+    let mut node: Node = relational(tokens, env);
+    loop {
+        if consume(tokens, "==") {
+            let (lhs, rhs) = usual_arith_conv(node, relational(tokens, env));
+            node = ND_EQ { lhs, rhs }
+        } else if consume(tokens, "!=") {
+            let (lhs, rhs) = usual_arith_conv(node, relational(tokens, env));
+            node = ND_NE { lhs, rhs }
+        } else {
+            return node;
+        }
+    }
+}
+
+// relational = add("<" add | "<=" add | ">" add | ">=" add)*
+fn relational(tokens: &mut VecDeque<Token>, env: &mut Env) -> Node {
+    let mut node: Node = shift(tokens, env);
+    loop {
+        if consume(tokens, "<") {
+            let (lhs, rhs) = usual_arith_conv(node, shift(tokens, env));
+            node = ND_LT { lhs, rhs }
+        } else if consume(tokens, "<=") {
+            let (lhs, rhs) = usual_arith_conv(node, shift(tokens, env));
+            node = ND_LE { lhs, rhs }
+        } else if consume(tokens, ">") {
+            let (lhs, rhs) = usual_arith_conv(shift(tokens, env), node);
+            node = ND_LT { lhs, rhs }
+        } else if consume(tokens, ">=") {
+            let (lhs, rhs) = usual_arith_conv(shift(tokens, env), node);
+            node = ND_LE { lhs, rhs }
+        } else {
+            return node;
+        }
+    }
+}
+
+// shift      = add ("<<" add | ">>" add)*
+//
+// シフトの両辺はそれぞれ整数拡張するだけで、結果は左辺の型になる
+fn shift(tokens: &mut VecDeque<Token>, env: &mut Env) -> Node {
+    let mut node: Node = add(tokens, env);
+    loop {
+        if consume(tokens, "<<") {
+            let rhs = add(tokens, env);
+            node = ND_SHL {
+                lhs: Box::new(int_promote(node)),
+                rhs: Box::new(int_promote(rhs)),
+            };
+        } else if consume(tokens, ">>") {
+            let rhs = add(tokens, env);
+            node = ND_SHR {
+                lhs: Box::new(int_promote(node)),
+                rhs: Box::new(int_promote(rhs)),
+            };
         } else {
             return node;
         }
@@ -343,106 +2492,492 @@ fn relational(tokens: &mut VecDeque<Token>, lvars: &mut VecDeque<LVar>) -> Node
 }
 
 // add        = mul ("+" mul | "-" mul)*
-fn add(tokens: &mut VecDeque<Token>, lvars: &mut VecDeque<LVar>) -> Node {
-    let mut node: Node = mul(tokens, lvars);
+fn add(tokens: &mut VecDeque<Token>, env: &mut Env) -> Node {
+    let mut node: Node = mul(tokens, env);
     loop {
         if consume(tokens, "+") {
-            node = ND_ADD {
-                lhs: Box::new(node),
-                rhs: Box::new(mul(tokens, lvars)),
-            }
+            node = new_add(node, mul(tokens, env));
         } else if consume(tokens, "-") {
-            node = ND_SUB {
-                lhs: Box::new(node),
-                rhs: Box::new(mul(tokens, lvars)),
-            }
+            node = new_sub(node, mul(tokens, env));
         } else {
             return node;
         }
     }
 }
 
-// mul        = unary ("*" unary | "/" unary)*
-fn mul(tokens: &mut VecDeque<Token>, lvars: &mut VecDeque<LVar>) -> Node {
-    let mut node: Node = unary(tokens, lvars);
+// ポインタに整数を足すときは、指す先の型のサイズ倍する
+fn new_add(lhs: Node, rhs: Node) -> Node {
+    let (lty, rty) = (lhs.ty(), rhs.ty());
+    if lty.is_integer() && rty.is_integer() {
+        let (lhs, rhs) = usual_arith_conv(lhs, rhs);
+        return ND_ADD { lhs, rhs };
+    }
+    if !lhs.ty().decay().is_scalar() || !rhs.ty().decay().is_scalar() {
+        eprintln!("整数かポインタでない値を足すことはできません");
+        std::process::exit(1);
+    }
+    if lty.base().is_some() && rty.base().is_some() {
+        eprintln!("ポインタ同士を足すことはできません");
+        std::process::exit(1);
+    }
+    // num + ptr は ptr + num に揃える
+    let (ptr, num) = if lty.base().is_some() {
+        (lhs, rhs)
+    } else {
+        (rhs, lhs)
+    };
+    let size = ptr.ty().base().unwrap().size();
+    return ND_ADD {
+        lhs: Box::new(ptr),
+        rhs: scale(num, size),
+    };
+}
+
+// ポインタに足し引きする整数を、ポインタと同じ幅で要素のサイズ倍する
+fn scale(num: Node, size: u32) -> Box<Node> {
+    let (lhs, rhs) = usual_arith_conv(new_cast(num, TY_LONG), new_num(size as i64));
+    return Box::new(ND_MUL { lhs, rhs });
+}
+
+// ポインタ同士の引き算は要素数を返す
+fn new_sub(lhs: Node, rhs: Node) -> Node {
+    let (lty, rty) = (lhs.ty(), rhs.ty());
+    if lty.is_integer() && rty.is_integer() {
+        let (lhs, rhs) = usual_arith_conv(lhs, rhs);
+        return ND_SUB { lhs, rhs };
+    }
+    if !lhs.ty().decay().is_scalar() || !rhs.ty().decay().is_scalar() {
+        eprintln!("整数かポインタでない値を引くことはできません");
+        std::process::exit(1);
+    }
+    if let (Some(lbase), Some(rbase)) = (lty.base(), rty.base()) {
+        if lbase != rbase {
+            eprintln!("異なる型のポインタ同士を引くことはできません");
+            std::process::exit(1);
+        }
+        let size = lbase.size() as i64;
+        let diff = ND_SUB {
+            lhs: Box::new(lhs),
+            rhs: Box::new(rhs),
+        };
+        let (lhs, rhs) = usual_arith_conv(diff, new_num(size));
+        return ND_DIV { lhs, rhs };
+    }
+    if let Some(base) = lty.base() {
+        let size = base.size();
+        return ND_SUB {
+            lhs: Box::new(lhs),
+            rhs: scale(rhs, size),
+        };
+    }
+    eprintln!("整数からポインタを引くことはできません");
+    std::process::exit(1);
+}
+
+// mul        = cast ("*" cast | "/" cast | "%" cast)*
+fn mul(tokens: &mut VecDeque<Token>, env: &mut Env) -> Node {
+    let mut node: Node = cast(tokens, env);
     loop {
         if consume(tokens, "*") {
-            node = ND_MUL {
-                lhs: Box::new(node),
-                rhs: Box::new(unary(tokens, lvars)),
-            }
+            node = new_binary("*", node, cast(tokens, env));
         } else if consume(tokens, "/") {
-            node = ND_DIV {
-                lhs: Box::new(node),
-                rhs: Box::new(unary(tokens, lvars)),
-            }
+            node = new_binary("/", node, cast(tokens, env));
+        } else if consume(tokens, "%") {
+            node = new_binary("%", node, cast(tokens, env));
         } else {
             return node;
         }
     }
 }
 
-// unary      = ("+" | "-")? primary
-fn unary(tokens: &mut VecDeque<Token>, lvars: &mut VecDeque<LVar>) -> Node {
-    if consume(tokens, "+") {
-        return unary(tokens, lvars);
+// cast       = "(" type-name ")" cast | unary
+//
+// "(" の次が型名なら、括弧で囲まれた式ではなくキャスト
+fn cast(tokens: &mut VecDeque<Token>, env: &mut Env) -> Node {
+    if peek(tokens, "(") && is_typename_at(tokens, 1, env) {
+        expect(tokens, "(");
+        let ty = type_name(tokens, env);
+        expect(tokens, ")");
+        let expr = cast(tokens, env);
+        // void へのキャストは値を捨てるだけなので、どんな式でもよい
+        if ty != TY_VOID && !(ty.is_scalar() && expr.ty().decay().is_scalar()) {
+            eprintln!("スカラー型以外のキャストはできません");
+            std::process::exit(1);
+        }
+        // 型が同じでもキャストの結果は左辺値ではないので、必ずノードを作る
+        return ND_CAST {
+            expr: Box::new(expr),
+            ty,
+        };
+    }
+    return unary(tokens, env);
+}
+
+// unary      = ("+" | "-" | "*" | "&" | "!" | "~") cast
+//            | ("++" | "--") unary
+//            | "sizeof" unary
+//            | "sizeof" "(" type-name ")"
+//            | "_Alignof" "(" type-name ")"
+//            | postfix
+fn unary(tokens: &mut VecDeque<Token>, env: &mut Env) -> Node {
+    if peek(tokens, "sizeof") && is_typename_at(tokens, 2, env) && tokens[1].str == "(" {
+        expect(tokens, "sizeof");
+        expect(tokens, "(");
+        let ty = type_name(tokens, env);
+        expect(tokens, ")");
+        return new_cast(new_num(size_of(&ty) as i64), TY_ULONG);
+    } else if consume(tokens, "_Alignof") {
+        expect(tokens, "(");
+        let ty = type_name(tokens, env);
+        expect(tokens, ")");
+        return new_cast(new_num(align_of(&ty) as i64), TY_ULONG);
+    } else if consume(tokens, "sizeof") {
+        // オペランドは評価せず、型のサイズだけを使う
+        let node = unary(tokens, env);
+        return new_cast(new_num(size_of(&node.ty()) as i64), TY_ULONG);
+    } else if consume(tokens, "++") {
+        // ++a は a += 1 と同じ
+        let node = unary(tokens, env);
+        return new_op_assign(node, new_num(1), "+", env);
+    } else if consume(tokens, "--") {
+        let node = unary(tokens, env);
+        return new_op_assign(node, new_num(1), "-", env);
+    } else if consume(tokens, "+") {
+        return cast(tokens, env);
     } else if consume(tokens, "-") {
-        return ND_SUB {
-            lhs: Box::new(ND_NUM(0)),
-            rhs: Box::new(unary(tokens, lvars)),
+        return new_sub(new_num(0), cast(tokens, env));
+    } else if consume(tokens, "&") {
+        return ND_ADDR {
+            expr: Box::new(cast(tokens, env)),
+        };
+    } else if consume(tokens, "~") {
+        return ND_BITNOT {
+            expr: Box::new(int_promote(cast(tokens, env))),
+        };
+    } else if consume(tokens, "!") {
+        return ND_NOT {
+            expr: Box::new(scalar(cast(tokens, env))),
+        };
+    } else if consume(tokens, "*") {
+        let node = cast(tokens, env);
+        // 関数の参照外しは関数そのもの
+        if node.ty().is_func() {
+            return node;
+        }
+        if node.ty().base().is_none() {
+            eprintln!("ポインタではない値を参照外しすることはできません");
+            std::process::exit(1);
+        }
+        return ND_DEREF {
+            expr: Box::new(node),
         };
     } else {
-        return primary(tokens, lvars);
+        return postfix(tokens, env);
+    }
+}
+
+// GCCに合わせて void の大きさは1とする
+fn size_of(ty: &Type) -> u32 {
+    if ty.is_incomplete() && *ty != TY_VOID {
+        eprintln!("不完全な型の大きさはわかりません");
+        std::process::exit(1);
+    }
+    return ty.size();
+}
+
+fn align_of(ty: &Type) -> u32 {
+    if ty.is_incomplete() && *ty != TY_VOID {
+        eprintln!("不完全な型のアライメントはわかりません");
+        std::process::exit(1);
+    }
+    return ty.align();
+}
+
+// postfix    = primary ("[" expr "]" | "." ident | "->" ident | "++" | "--" | func-args)*
+fn postfix(tokens: &mut VecDeque<Token>, env: &mut Env) -> Node {
+    let mut node = primary(tokens, env);
+    loop {
+        if consume(tokens, "(") {
+            node = funcall(tokens, node, env);
+        } else if consume(tokens, "[") {
+            // a[i] は *(a+i) と同じ
+            let idx = expr(tokens, env);
+            expect(tokens, "]");
+            node = new_add(node, idx);
+            if node.ty().base().is_none() {
+                eprintln!("配列でもポインタでもない値に添字を付けることはできません");
+                std::process::exit(1);
+            }
+            node = ND_DEREF {
+                expr: Box::new(node),
+            };
+        } else if consume(tokens, ".") {
+            node = struct_ref(tokens, node);
+        } else if consume(tokens, "->") {
+            // p->x は (*p).x と同じ
+            if node.ty().base().is_none() {
+                eprintln!("ポインタではない値に->を使うことはできません");
+                std::process::exit(1);
+            }
+            node = ND_DEREF {
+                expr: Box::new(node),
+            };
+            node = struct_ref(tokens, node);
+        } else if consume(tokens, "++") {
+            node = new_post_inc_dec(node, "+", env);
+        } else if consume(tokens, "--") {
+            node = new_post_inc_dec(node, "-", env);
+        } else {
+            return node;
+        }
+    }
+}
+
+// a++ は tmp = &a, old = *tmp, *tmp = *tmp + 1, old として、変更前の値を返す
+// (a += 1) - 1 で計算すると、_Bool では元の値に戻らない
+fn new_post_inc_dec(node: Node, op: &str, env: &mut Env) -> Node {
+    let ty = node.ty();
+    if ty.is_array() {
+        eprintln!("配列に代入することはできません");
+        std::process::exit(1);
+    }
+    let tmp = env.new_temp(ty.clone().pointer_to());
+    let old = env.new_temp(ty.clone());
+    let deref = || ND_DEREF {
+        expr: Box::new(tmp.clone()),
+    };
+    let addr = ND_ASSIGN {
+        lhs: Box::new(tmp.clone()),
+        rhs: Box::new(ND_ADDR {
+            expr: Box::new(node),
+        }),
+    };
+    let save = ND_ASSIGN {
+        lhs: Box::new(old.clone()),
+        rhs: Box::new(deref()),
+    };
+    let update = ND_ASSIGN {
+        lhs: Box::new(deref()),
+        rhs: Box::new(new_cast(new_binary(op, deref(), new_num(1)), ty.clone())),
+    };
+    // 結果は左辺値ではないので、同じ型へのキャストで包む
+    let result = ND_CAST {
+        expr: Box::new(old),
+        ty,
+    };
+    return ND_COMMA {
+        lhs: Box::new(addr),
+        rhs: Box::new(ND_COMMA {
+            lhs: Box::new(save),
+            rhs: Box::new(ND_COMMA {
+                lhs: Box::new(update),
+                rhs: Box::new(result),
+            }),
+        }),
+    };
+}
+
+fn struct_ref(tokens: &mut VecDeque<Token>, node: Node) -> Node {
+    let st = match node.ty() {
+        TY_STRUCT(st) => st,
+        _ => {
+            eprintln!("構造体ではない値のメンバを参照することはできません");
+            std::process::exit(1);
+        }
+    };
+    let name = match consume_tk(tokens, TK_IDENT) {
+        Some(token) => token.str,
+        None => {
+            eprintln!("メンバ名を期待しましたが、ありませんでした");
+            std::process::exit(1);
+        }
+    };
+    match st.find_member(&name) {
+        Some(member) => {
+            return ND_MEMBER {
+                expr: Box::new(node),
+                offset: member.offset,
+                ty: member.ty,
+            };
+        }
+        None => {
+            eprintln!("{}というメンバはありません", name);
+            std::process::exit(1);
+        }
     }
 }
 
 // func-args = "(" (assign ("," assign)*)? ")"
-fn func_args(tokens: &mut VecDeque<Token>, lvars: &mut VecDeque<LVar>) -> Vec<Node> {
+fn func_args(tokens: &mut VecDeque<Token>, env: &mut Env) -> Vec<Node> {
     if consume(tokens, ")") {
         return Vec::new();
     }
-    let mut args: Vec<Node> = vec![assign(tokens, lvars)];
+    let mut args: Vec<Node> = vec![assign(tokens, env)];
     while consume(tokens, ",") {
-        args.push(assign(tokens, lvars));
+        args.push(assign(tokens, env));
     }
     expect(tokens, ")");
     return args;
 }
 
-// primary    = num | ident func-args? | "(" expr ")"
-fn primary(tokens: &mut VecDeque<Token>, lvars: &mut VecDeque<LVar>) -> Node {
+// 関数名か関数ポインタを呼び出す
+fn funcall(tokens: &mut VecDeque<Token>, func: Node, env: &mut Env) -> Node {
+    let (ret, params) = match func.ty().as_func() {
+        Some(TY_FUNC { ret, params }) => (*ret.clone(), params.clone()),
+        _ => {
+            eprintln!("関数ではない値を呼び出すことはできません");
+            std::process::exit(1);
+        }
+    };
+    let mut args = func_args(tokens, env);
+    if args.len() > ARG_MAX {
+        eprintln!("引数は{}個までしか渡せません", ARG_MAX);
+        std::process::exit(1);
+    }
+    // プロトタイプがあれば、引数の数と型を確かめてその型に変換して渡す
+    if let Some(params) = params {
+        if args.len() != params.len() {
+            eprintln!(
+                "引数の数が一致しません ({}個必要ですが、{}個渡されています)",
+                params.len(),
+                args.len()
+            );
+            std::process::exit(1);
+        }
+        args = args
+            .into_iter()
+            .zip(params)
+            .enumerate()
+            .map(|(i, (arg, ty))| {
+                if !is_assignable(&ty, &arg) {
+                    eprintln!("{}番目の引数の型が一致しません", i + 1);
+                    std::process::exit(1);
+                }
+                new_cast(arg, ty)
+            })
+            .collect();
+    }
+    return ND_FUNCALL {
+        func: Box::new(func),
+        args,
+        ty: ret,
+    };
+}
+
+// 引数として渡せるか
+// ポインタと整数の間は、定数0をヌルポインタとして渡す場合だけ認める
+fn is_assignable(ty: &Type, node: &Node) -> bool {
+    let from = node.ty().decay();
+    if ty.is_struct() || from.is_struct() {
+        return *ty == from;
+    }
+    if ty.base().is_some() {
+        return from.base().is_some() || matches!(node, ND_NUM { val: 0, .. });
+    }
+    return from.is_integer();
+}
+
+// primary    = num | ident | "(" expr ")"
+fn primary(tokens: &mut VecDeque<Token>, env: &mut Env) -> Node {
     if consume(tokens, "(") {
-        let node = expr(tokens, lvars);
+        let node = expr(tokens, env);
         expect(tokens, ")");
         return node;
     } else if let Some(token) = consume_tk(tokens, TK_IDENT) {
-        if consume(tokens, "(") {
-            let args = func_args(tokens, lvars);
-            return ND_FUNCALL {
+        if env.find_var(&token.str).is_none() && peek(tokens, "(") {
+            let ty = env.declare_implicit_func(token.str.clone());
+            return ND_FUNC {
                 name: token.str,
-                args,
+                ty,
             };
-        } else {
-            return local_var(token, lvars);
+        }
+        return variable(token, env);
+    }
+    match expect_number(tokens) {
+        Ok(val) => return new_num(val as i64),
+        Err(msg) => {
+            eprintln!("{}", msg);
+            std::process::exit(1);
         }
     }
-    return ND_NUM(expect_number(tokens).unwrap());
 }
 
-fn local_var(token: Token, lvars: &mut VecDeque<LVar>) -> Node {
-    return if let Some(lvar) = find_lvar(&token, lvars) {
-        ND_LVAR {
-            offset: lvar.offset,
-        }
+// 整数リテラルは int に収まらなければ long になる
+fn new_num(val: i64) -> Node {
+    let ty = if (i32::MIN as i64..=i32::MAX as i64).contains(&val) {
+        TY_INT
     } else {
-        let offset = match lvars.front() {
-            Some(lv) => lv.offset + 8,
-            None => 0,
-        };
-        lvars.push_front(LVar {
-            name: token.str.clone(),
-            offset,
-        });
-        ND_LVAR { offset }
+        TY_LONG
+    };
+    return ND_NUM { val, ty };
+}
+
+fn new_cast(expr: Node, ty: Type) -> Node {
+    if expr.ty() == ty {
+        return expr;
+    }
+    return ND_CAST {
+        expr: Box::new(expr),
+        ty,
     };
 }
+
+// 算術型の二項演算の両辺を、整数拡張と通常の算術型変換で同じ型に揃える
+fn usual_arith_conv(lhs: Node, rhs: Node) -> (Box<Node>, Box<Node>) {
+    let (lhs, rhs) = (scalar(lhs), scalar(rhs));
+    let ty = common_type(&lhs.ty(), &rhs.ty());
+    return (Box::new(new_cast(lhs, ty.clone())), Box::new(new_cast(rhs, ty)));
+}
+
+// 整数型の値だけを取る演算子の両辺を揃える
+fn int_arith_conv(lhs: Node, rhs: Node) -> (Box<Node>, Box<Node>) {
+    return usual_arith_conv(int_promote(lhs), int_promote(rhs));
+}
+
+// int より小さい整数型は int にする
+fn int_promote(node: Node) -> Node {
+    let ty = node.ty();
+    if !ty.is_integer() {
+        eprintln!("整数型の値が必要です");
+        std::process::exit(1);
+    }
+    return new_cast(node, common_type(&ty, &ty));
+}
+
+fn variable(token: Token, env: &Env) -> Node {
+    match env.find_var(&token.str) {
+        Some(SYM_LVAR { offset, ty, align }) => {
+            return ND_LVAR {
+                offset: *offset,
+                ty: ty.clone(),
+                align: *align,
+            };
+        }
+        Some(SYM_GVAR { ty }) => {
+            return ND_GVAR {
+                name: token.str,
+                ty: ty.clone(),
+            };
+        }
+        Some(SYM_ENUM_CONST { val }) => return new_num(*val),
+        Some(SYM_FUNC { ty, .. }) => {
+            return ND_FUNC {
+                name: token.str,
+                ty: ty.clone(),
+            };
+        }
+        Some(SYM_TYPEDEF { .. }) => {
+            eprintln!("型名{}を式の中で使うことはできません", token.str);
+            std::process::exit(1);
+        }
+        None => {
+            eprintln!("{}は宣言されていません", token.str);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn declare_lvar(token: Token, ty: Type, align: u32, env: &mut Env) -> Node {
+    let offset = env.declare_lvar(token.str, ty.clone(), align);
+    return ND_LVAR { offset, ty, align };
+}
@@ -17,7 +17,7 @@ use TokenKind::*;
 #[derive(Clone, Debug)]
 pub struct Token {
     pub kind: TokenKind,
-    pub val: Option<u32>,
+    pub val: Option<u64>,
     pub str: String,
 }
 
@@ -57,36 +57,37 @@ pub fn tokenize(chars: Vec<char>) -> VecDeque<Token> {
                 str: String::from("if"),
             });
             continue;
-        } else if startwith_keyword(&chars, &mut i, "else") {
+        } else if let Some(str) = startwith_reserved_keyword(&chars, &mut i) {
             tokens.push_back(Token {
                 kind: TK_RESERVED,
                 val: None,
-                str: String::from("else"),
+                str,
             });
             continue;
         } else if let Some(str) = startwith_ident(&chars, &mut i) {
             tokens.push_back(Token {
                 kind: TK_IDENT,
                 val: None,
-                str: str,
+                str,
             });
             continue;
         } else if let Some(str) = startwith(
             &chars,
             &mut i,
-            // 2文字のトークンを先にチェックする
+            // 長いトークンを先にチェックする
             vec![
-                "<=", ">=", "==", "!=", "+", "-", "*", "/", "(", ")", "<", ">", ";", "=", "{", "}",
-                ",",
+                "<<=", ">>=", "<=", ">=", "==", "!=", "->", "&&", "||", "<<", ">>", "+=", "-=", "*=",
+                "/=", "%=", "&=", "|=", "^=", "++", "--", "+", "-", "*", "/", "%", "(", ")", "<", ">",
+                ";", "=", "{", "}", ",", "&", "|", "^", "~", "[", "]", ".", "!", "?", ":",
             ],
         ) {
             tokens.push_back(Token {
                 kind: TK_RESERVED,
                 val: None,
-                str: str,
+                str,
             });
             continue;
-        } else if c.is_digit(10) {
+        } else if c.is_ascii_digit() {
             tokens.push_back(Token {
                 kind: TK_NUM,
                 val: strtol(&chars, &mut i),
@@ -101,31 +102,49 @@ pub fn tokenize(chars: Vec<char>) -> VecDeque<Token> {
     return tokens;
 }
 
-fn startwith_keyword(chars: &Vec<char>, ind: &mut usize, keyword: &str) -> bool {
-    let i = ind.clone();
+// TK_RESERVEDとして扱うキーワード
+const KEYWORDS: [&str; 25] = [
+    "else", "sizeof", "_Alignof", "_Alignas", "void", "_Bool", "char", "short", "int", "long",
+    "signed", "unsigned", "struct", "union", "enum", "typedef", "static", "extern", "break",
+    "continue", "do", "switch", "case", "default", "goto",
+];
+
+fn startwith_reserved_keyword(chars: &[char], ind: &mut usize) -> Option<String> {
+    for keyword in KEYWORDS.iter() {
+        if startwith_keyword(chars, ind, keyword) {
+            return Some(keyword.to_string());
+        }
+    }
+    return None;
+}
+
+fn startwith_keyword(chars: &[char], ind: &mut usize, keyword: &str) -> bool {
+    let i = *ind;
     let klen = keyword.len();
     let sub_chars = &chars[i..cmp::min(i + klen, chars.len())]
         .iter()
         .collect::<String>();
-    if sub_chars.to_string() == keyword.to_string() {
-        if i + klen == chars.len() || !chars[i + klen].is_ascii_lowercase() {
-            *ind += klen;
-            return true;
-        }
+    if sub_chars == keyword && (i + klen == chars.len() || !is_ident_char(chars[i + klen])) {
+        *ind += klen;
+        return true;
     }
     return false;
 }
 
-fn startwith_ident(chars: &Vec<char>, ind: &mut usize) -> Option<String> {
-    let mut i = ind.clone();
+fn is_ident_char(c: char) -> bool {
+    return c.is_ascii_alphanumeric() || c == '_';
+}
+
+fn startwith_ident(chars: &[char], ind: &mut usize) -> Option<String> {
+    let mut i = *ind;
     let mut char_vec: Vec<char> = Vec::new();
-    if chars[i].is_ascii_lowercase() {
+    if chars[i].is_ascii_alphabetic() || chars[i] == '_' {
         char_vec.push(chars[i]);
         i += 1;
     } else {
         return None;
     }
-    while chars[i].is_ascii_lowercase() || chars[i].is_digit(10) {
+    while i < chars.len() && is_ident_char(chars[i]) {
         char_vec.push(chars[i]);
         i += 1;
     }
@@ -133,13 +152,13 @@ fn startwith_ident(chars: &Vec<char>, ind: &mut usize) -> Option<String> {
     return Some(char_vec.iter().collect());
 }
 
-fn startwith(chars: &Vec<char>, ind: &mut usize, patterns: Vec<&str>) -> Option<String> {
-    let i = ind.clone();
+fn startwith(chars: &[char], ind: &mut usize, patterns: Vec<&str>) -> Option<String> {
+    let i = *ind;
     for pat in patterns {
         let sub_chars = &chars[i..cmp::min(i + pat.len(), chars.len())]
             .iter()
             .collect::<String>();
-        if sub_chars.to_string() == pat.to_string() {
+        if sub_chars == pat {
             *ind += sub_chars.len();
             return Some(sub_chars.to_string());
         }
@@ -147,13 +166,20 @@ fn startwith(chars: &Vec<char>, ind: &mut usize, patterns: Vec<&str>) -> Option<
     return None;
 }
 
-fn strtol(chars: &Vec<char>, ind: &mut usize) -> Option<u32> {
+// 接尾辞のない整数リテラルは long に収まらなければならない
+fn strtol(chars: &[char], ind: &mut usize) -> Option<u64> {
     if let Some(d) = chars[*ind].to_digit(10) {
         *ind += 1;
-        let mut r: u32 = d;
+        let mut r: u64 = d as u64;
         while *ind < chars.len() {
             if let Some(d) = chars[*ind].to_digit(10) {
-                r = 10 * r + d;
+                r = match r.checked_mul(10).and_then(|r| r.checked_add(d as u64)) {
+                    Some(r) if r <= i64::MAX as u64 => r,
+                    _ => {
+                        eprintln!("整数リテラルが大きすぎます");
+                        std::process::exit(1);
+                    }
+                };
                 *ind += 1;
             } else {
                 break;
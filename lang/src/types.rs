@@ -0,0 +1,346 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Type {
+    TY_VOID,
+    TY_BOOL,
+    TY_CHAR,
+    TY_UCHAR,
+    TY_SHORT,
+    TY_USHORT,
+    TY_INT,
+    TY_UINT,
+    TY_LONG,
+    TY_ULONG,
+    TY_PTR { to: Box<Type> },
+    // int a[] のように長さを省略した配列は len が None
+    TY_ARRAY { base: Box<Type>, len: Option<u32> },
+    TY_STRUCT(StructRef),
+    // params が None のときは引数の型を指定しない古い形式の宣言 f()
+    TY_FUNC {
+        ret: Box<Type>,
+        params: Option<Vec<Type>>,
+    },
+}
+
+use Type::*;
+
+#[derive(Clone, Debug)]
+pub struct Member {
+    pub name: String,
+    pub ty: Type,
+    pub offset: u32,
+}
+
+#[derive(Debug)]
+pub struct StructDef {
+    pub is_union: bool,
+    // 定義の本体をまだ読んでいない間は None
+    pub members: Option<Vec<Member>>,
+    pub size: u32,
+    pub align: u32,
+}
+
+// 自分自身へのポインタをメンバに持てるように、構造体の定義は参照で共有する
+// 同じ定義を指しているときだけ同じ型とみなす
+#[derive(Clone)]
+pub struct StructRef(Rc<RefCell<StructDef>>);
+
+impl StructRef {
+    // 不完全型として作っておき、後でset_membersで中身を決める
+    pub fn incomplete(is_union: bool) -> StructRef {
+        return StructRef(Rc::new(RefCell::new(StructDef {
+            is_union,
+            members: None,
+            size: 0,
+            align: 1,
+        })));
+    }
+
+    pub fn is_complete(&self) -> bool {
+        return self.0.borrow().members.is_some();
+    }
+
+    pub fn is_union(&self) -> bool {
+        return self.0.borrow().is_union;
+    }
+
+    // System V ABI に従ってメンバを配置する
+    // 共用体のメンバはすべてオフセット0に置く
+    // _Alignas を付けたメンバは、指定したアライメントに揃える
+    pub fn set_members(&self, decls: Vec<(String, Type, u32)>) {
+        let is_union = self.is_union();
+        let mut members = Vec::new();
+        let mut offset = 0;
+        let mut size = 0;
+        let mut align = 1;
+        for (name, ty, member_align) in decls {
+            if !is_union {
+                offset = align_to(offset, member_align);
+            }
+            if member_align > align {
+                align = member_align;
+            }
+            let end = offset + ty.size();
+            if end > size {
+                size = end;
+            }
+            members.push(Member { name, ty, offset });
+            if !is_union {
+                offset = end;
+            }
+        }
+        let mut def = self.0.borrow_mut();
+        def.members = Some(members);
+        def.size = align_to(size, align);
+        def.align = align;
+    }
+
+    pub fn members(&self) -> Vec<Member> {
+        return self.0.borrow().members.clone().unwrap_or_default();
+    }
+
+    // 名前のないメンバ(無名の構造体・共用体)の中も探す
+    pub fn find_member(&self, name: &str) -> Option<Member> {
+        let def = self.0.borrow();
+        for member in def.members.as_ref()? {
+            if member.name == name {
+                return Some(member.clone());
+            }
+            if let (true, TY_STRUCT(inner)) = (member.name.is_empty(), &member.ty) {
+                if let Some(found) = inner.find_member(name) {
+                    return Some(Member {
+                        offset: member.offset + found.offset,
+                        ..found
+                    });
+                }
+            }
+        }
+        return None;
+    }
+
+    // 名前のないメンバの中のメンバも含めた、メンバの名前
+    pub fn member_names(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        for member in self.members() {
+            match &member.ty {
+                TY_STRUCT(inner) if member.name.is_empty() => names.extend(inner.member_names()),
+                _ => names.push(member.name),
+            }
+        }
+        return names;
+    }
+}
+
+impl PartialEq for StructRef {
+    fn eq(&self, other: &StructRef) -> bool {
+        return Rc::ptr_eq(&self.0, &other.0);
+    }
+}
+
+// メンバが自分自身を指すことがあるので、中身は表示しない
+impl fmt::Debug for StructRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "struct@{:p}", Rc::as_ptr(&self.0));
+    }
+}
+
+impl Type {
+    pub fn size(&self) -> u32 {
+        match self {
+            // GCCに合わせて、voidと関数の大きさは1とする
+            TY_VOID | TY_FUNC { .. } => 1,
+            TY_BOOL | TY_CHAR | TY_UCHAR => 1,
+            TY_SHORT | TY_USHORT => 2,
+            TY_INT | TY_UINT => 4,
+            TY_LONG | TY_ULONG => 8,
+            TY_PTR { to: _ } => 8,
+            // 大きさが u32 に収まらない配列は作らないようにしている
+            TY_ARRAY { base, len } => base.size().checked_mul(len.unwrap_or(0)).unwrap(),
+            TY_STRUCT(st) => st.0.borrow().size,
+        }
+    }
+
+    pub fn align(&self) -> u32 {
+        match self {
+            TY_ARRAY { base, len: _ } => base.align(),
+            TY_STRUCT(st) => st.0.borrow().align,
+            _ => self.size(),
+        }
+    }
+
+    pub fn pointer_to(self) -> Type {
+        return TY_PTR { to: Box::new(self) };
+    }
+
+    pub fn array_of(self, len: u32) -> Type {
+        return TY_ARRAY {
+            base: Box::new(self),
+            len: Some(len),
+        };
+    }
+
+    // 長さは初期化子の要素数から後で決める
+    pub fn array_of_unknown_len(self) -> Type {
+        return TY_ARRAY {
+            base: Box::new(self),
+            len: None,
+        };
+    }
+
+    pub fn func_returning(self, params: Option<Vec<Type>>) -> Type {
+        return TY_FUNC {
+            ret: Box::new(self),
+            params,
+        };
+    }
+
+    // ポインタが指す先の型、または配列の要素の型
+    pub fn base(&self) -> Option<&Type> {
+        match self {
+            TY_PTR { to } => Some(to),
+            TY_ARRAY { base, len: _ } => Some(base),
+            _ => None,
+        }
+    }
+
+    // 式の中の配列は先頭要素へのポインタ、関数は関数へのポインタとして扱う
+    pub fn decay(self) -> Type {
+        match self {
+            TY_ARRAY { base, len: _ } => TY_PTR { to: base },
+            TY_FUNC { .. } => self.pointer_to(),
+            _ => self,
+        }
+    }
+
+    pub fn is_func(&self) -> bool {
+        return matches!(self, TY_FUNC { .. });
+    }
+
+    // 関数、または関数へのポインタの型から関数の型を取り出す
+    pub fn as_func(&self) -> Option<&Type> {
+        match self {
+            TY_FUNC { .. } => Some(self),
+            TY_PTR { to } if to.is_func() => Some(to),
+            _ => None,
+        }
+    }
+
+    pub fn is_array(&self) -> bool {
+        return matches!(self, TY_ARRAY { .. });
+    }
+
+    pub fn is_struct(&self) -> bool {
+        return matches!(self, TY_STRUCT(_));
+    }
+
+    // 整数、ポインタのように1つの値を持つ型
+    pub fn is_scalar(&self) -> bool {
+        return self.is_integer() || matches!(self, TY_PTR { .. });
+    }
+
+    // 値を読み書きせず、アドレスのまま扱う型
+    pub fn is_aggregate(&self) -> bool {
+        return self.is_array() || self.is_struct();
+    }
+
+    // 大きさの決まっていない型
+    pub fn is_incomplete(&self) -> bool {
+        match self {
+            TY_VOID => true,
+            TY_STRUCT(st) => !st.is_complete(),
+            TY_ARRAY { base, len } => len.is_none() || base.is_incomplete(),
+            _ => false,
+        }
+    }
+
+    pub fn is_integer(&self) -> bool {
+        return matches!(
+            self,
+            TY_BOOL
+                | TY_CHAR
+                | TY_UCHAR
+                | TY_SHORT
+                | TY_USHORT
+                | TY_INT
+                | TY_UINT
+                | TY_LONG
+                | TY_ULONG
+        );
+    }
+
+    // ポインタはアドレスなので符号なしとして比較する
+    pub fn is_unsigned(&self) -> bool {
+        return matches!(
+            self,
+            TY_BOOL
+                | TY_UCHAR
+                | TY_USHORT
+                | TY_UINT
+                | TY_ULONG
+                | TY_PTR { .. }
+                | TY_ARRAY { .. }
+                | TY_FUNC { .. }
+        );
+    }
+}
+
+// ポインタ同士の引き算の結果の型
+pub fn ptrdiff_t() -> Type {
+    return TY_LONG;
+}
+
+// 二項演算の結果の型
+pub fn common_type(lhs: &Type, rhs: &Type) -> Type {
+    if lhs.base().is_some() {
+        return lhs.clone().decay();
+    }
+    if rhs.base().is_some() {
+        return rhs.clone().decay();
+    }
+    // int より小さい型は int として計算する
+    if lhs.size() < 4 && rhs.size() < 4 {
+        return TY_INT;
+    }
+    if lhs.size() != rhs.size() {
+        return if lhs.size() > rhs.size() {
+            lhs.clone()
+        } else {
+            rhs.clone()
+        };
+    }
+    return if rhs.is_unsigned() {
+        rhs.clone()
+    } else {
+        lhs.clone()
+    };
+}
+
+// 同じ関数の2つの宣言を合わせた型
+// 引数を指定しない宣言は、引数を指定した宣言と両立する
+pub fn composite_type(a: &Type, b: &Type) -> Option<Type> {
+    match (a, b) {
+        (
+            TY_FUNC {
+                ret: ret_a,
+                params: params_a,
+            },
+            TY_FUNC {
+                ret: ret_b,
+                params: params_b,
+            },
+        ) if ret_a == ret_b => match (params_a, params_b) {
+            (None, _) => return Some(b.clone()),
+            (_, None) => return Some(a.clone()),
+            (Some(pa), Some(pb)) if pa == pb => return Some(a.clone()),
+            _ => return None,
+        },
+        _ => return None,
+    }
+}
+
+pub fn align_to(n: u32, align: u32) -> u32 {
+    return n.div_ceil(align) * align;
+}
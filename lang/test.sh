@@ -29,6 +29,17 @@ assert() {
     fi
 }
 
+assert_error() {
+    input="$1"
+
+    if ./target/debug/lang "$input" > /dev/null 2>&1; then
+        echo "$input => error expected, but compiled"
+        exit 1
+    else
+        echo "$input => error"
+    fi
+}
+
 echo building...
 cargo build -q
 
@@ -63,16 +74,16 @@ assert 1 "main() {return 1>=0;}"
 assert 1 "main() {return 1>=1;}"
 assert 0 "main() {return 1>=2;}"
 
-assert 2 "main() {a=1;return a+1;}"
-assert 6 "main() {a=2;b=3;return a*b;}"
-assert 6 "main() {ab=2;cd=3;return ab*cd;}"
-assert 6 "main() {ab=2;cd=3;return ab*cd;}"
+assert 2 "main() {int a=1;return a+1;}"
+assert 6 "main() {int a=2;int b=3;return a*b;}"
+assert 6 "main() {int ab=2;int cd=3;return ab*cd;}"
+assert 6 "main() {int ab=2, cd=3;return ab*cd;}"
 
-assert 1 "main() {a=1;if (a==1) return a;}"
-assert 2 "main() {a=1;b=2;if (a!=1) return a; else return b;}"
-assert 16 "main() {a=1;while (a<10) a=a*2; return a;}"
-assert 8 "main() {a=1;for(c=0;c<3;c=c+1;)a=a*2;return a;}"
-assert 8 "main() {a=0;b=1;while(a<3){a=a+1;b=b*2;}return b;}"
+assert 1 "main() {int a=1;if (a==1) return a;}"
+assert 2 "main() {int a=1;int b=2;if (a!=1) return a; else return b;}"
+assert 16 "main() {int a=1;while (a<10) a=a*2; return a;}"
+assert 8 "main() {int a=1;int c;for(c=0;c<3;c=c+1)a=a*2;return a;}"
+assert 8 "main() {int a=0;int b=1;while(a<3){a=a+1;b=b*2;}return b;}"
 
 assert 3 "main() {return ret3();}"
 assert 5 "main() {return ret5();}"
@@ -84,4 +95,600 @@ assert 6 "main() {return f(2, 3);} f(x, y) {return x*y;}"
 assert 7 "main() {return f(1, 2, 3);} f(x, y, z) {return x+y*z;}"
 assert 55 'main() { return fib(9); } fib(x) { if (x<=1) return 1; return fib(x-1) + fib(x-2); }'
 
+assert 3 "main() {int a, b; a=1; b=2; return a+b;}"
+assert_error "main() {a=1;return a;}"
+assert_error "main() {int a; int a; return 0;}"
+
+assert 2 "main() {int a=1; {int a=2; return a;}}"
+assert 1 "main() {int a=1; {int a=2;} return a;}"
+assert 3 "main() {int a=1; {int b=2; a=a+b;} {int c=5;} return a;}"
+assert 7 "main() {int a=1; {int b=2; {int a=4; b=b+a;} a=a+b;} return a;}"
+assert_error "main() {{int a=1;} return a;}"
+assert_error "f(x) {int x; return x;} main() {return f(1);}"
+
+assert 3 "main() {int x=3; return *&x;}"
+assert 3 "main() {int x=3; int *y=&x; int **z=&y; return **z;}"
+assert 5 "main() {int x=3; int y=5; return *(&x-1);}"
+assert 3 "main() {int x=3; int y=5; return *(&y+1);}"
+assert 3 "main() {int x=3; int y=5; return *(1+&y);}"
+assert 5 "main() {int x=3; int *y=&x; *y=5; return x;}"
+assert 7 "main() {int x=3; int y=5; *(&x-1)=7; return y;}"
+assert 2 "main() {int x=3; int y=5; return &x-&y+1;}"
+assert 1 "int main() {int x=1; return x-x-x-x-x-x-x-x-x-x-x-x-x-x-x-x-x-x-x-x-x-x-x-x-x-x-x-x-x-x-x-x-x-x-x-x-x-x-x-x-x-x-x-x-x-x-x-x-x-x-x-x-x-x-x-x-x-x-x-x+59;}"
+assert_error "main() {int x=3; return *x;}"
+assert_error "main() {int x; int *p=&x; int *q=&x; return p+q;}"
+
+assert 3 "main() {int a[2]; *a=1; *(a+1)=2; int *p=a; return *p+*(p+1);}"
+assert 5 "main() {int a[3]; a[0]=1; a[1]=2; a[2]=4; return a[0]+a[2];}"
+assert 2 "main() {int a[3]; 1[a]=2; return a[1];}"
+assert 11 "main() {int m[3][4]; m[1][2]=11; return *(*(m+1)+2);}"
+assert 6 "main() {int m[2][3]; int *p=m; p[5]=6; return m[1][2];}"
+assert 2 "main() {int a[4]; return &a[3]-&a[1];}"
+assert 4 "main() {int x; return sizeof(x);}"
+assert 8 "main() {int *x; return sizeof x;}"
+assert 16 "main() {int a[4]; return sizeof(a);}"
+assert 48 "main() {int m[3][4]; return sizeof m;}"
+assert 16 "main() {int m[3][4]; return sizeof(m[1]);}"
+assert 8 "main() {int m[3][4]; return sizeof(m+1);}"
+assert 8 "main() {int a[4]; return sizeof(a+1);}"
+assert 1 "main() {int x=1; sizeof(x=5); return x;}"
+assert_error "main() {int a[2]; int b[2]; a=b; return 0;}"
+assert_error "main() {int x; return x[0];}"
+assert_error "int main() {int a[2000000000]; return 0;}"
+assert_error "int main() {char a[(long)1<<32]; return sizeof(a);}"
+assert_error "int a[1000000][1000000]; int main() {return 0;}"
+assert_error "int main() {char a[3000000000]; return 0;}"
+assert_error "int main() {char a[2000000000]; char b[2000000000]; return 0;}"
+assert_error "int main() {int a[] = {[2000000000]=1}; return 0;}"
+
+assert 1 "main() {char x; return sizeof(x);}"
+assert 2 "main() {short x; return sizeof(x);}"
+assert 2 "main() {short int x; return sizeof(x);}"
+assert 4 "main() {unsigned x; return sizeof(x);}"
+assert 8 "main() {long x; return sizeof(x);}"
+assert 8 "main() {long long int x; return sizeof(x);}"
+assert 8 "main() {unsigned long long x; return sizeof(x);}"
+assert 10 "main() {char a[10]; return sizeof(a);}"
+assert 3 "main() {char x=1; char y=2; return x+y;}"
+assert 1 "main() {char a[3]; a[0]=1; a[1]=2; a[2]=3; return a[2]-a[1];}"
+assert 1 "main() {char x=0; x=x-1; return x==-1;}"
+assert 255 "main() {unsigned char x=255; return x;}"
+assert 1 "main() {char x=255; return x==-1;}"
+assert 0 "main() {unsigned char x=255; return x==-1;}"
+assert 44 "main() {char x=300; return x;}"
+assert 1 "main() {short x=65535; return x==-1;}"
+assert 1 "main() {unsigned short x=65535; return x==65535;}"
+assert 1 "main() {int x=0; x=x-1; long y=x; return y<0;}"
+assert 1 "main() {unsigned x=0; x=x-1; long y=x; return y>0;}"
+assert 1 "main() {unsigned x=0; x=x-1; return x+1==0;}"
+assert 7 "main() {unsigned x=0; x=x-1; return x/613566756;}"
+assert 255 "main() {int x=0; x=x-7; return x/2+258;}"
+assert 1 "main() {unsigned x=0; x=x-1; unsigned y=1; return y<x;}"
+assert 0 "main() {int x=0; x=x-1; int y=1; return y<x;}"
+assert 1 "main() {long a[3]; return &a[2]-&a[1];}"
+assert 5 "main() {char c; short s; int i; long l; c=1; s=1; i=1; l=2; return c+s+i+l;}"
+assert 1 "int main() {long x = 4294967296; return x == (long)1 << 32;}"
+assert 8 "int main() {return sizeof(4294967296);}"
+assert 4 "int main() {return sizeof(2147483647);}"
+assert 1 "int main() {long x = 9223372036854775807; return x == ~((long)1 << 63);}"
+assert_error "int main() {long x = 9223372036854775808; return 0;}"
+assert_error "int main() {return 99999999999999999999;}"
+
+# 符号付きと符号なしの混在した演算 (期待値はGCCの結果)
+assert 0 "main() {int a=-1; unsigned b=1; return a<b;}"
+assert 0 "main() {int a=-1; unsigned long b=1; return a<b;}"
+assert 1 "main() {long a=-1; unsigned b=1; return a<b;}"
+assert 1 "main() {char a=-1; unsigned char b=1; return a<b;}"
+assert 1 "main() {short a=-1; unsigned short b=1; return a<b;}"
+assert 1 "main() {int a=-1; unsigned char b=255; return a<b;}"
+assert 1 "main() {int a=-1; unsigned short b=1; return a<b;}"
+assert 1 "main() {unsigned a=4294967295; int b=-1; return a==b;}"
+assert 1 "main() {unsigned long a=-1; long b=-1; return a==b;}"
+assert 1 "main() {char a=-1; unsigned b=4294967295; return a==b;}"
+assert 0 "main() {unsigned short a=65535; short b=-1; return a==b;}"
+assert 0 "main() {unsigned char a=255; char b=-1; return a==b;}"
+assert 1 "main() {unsigned char a=255; char b=-1; return a!=b;}"
+assert 1 "main() {int a=-1; unsigned b=0; return a>b;}"
+assert 1 "main() {int a=-1; unsigned b=0; return a>=b;}"
+assert 0 "main() {int a=-1; unsigned b=0; return a<=b;}"
+assert 1 "main() {long a=-1; unsigned long b=0; return a>b;}"
+assert 0 "main() {long a=-1; unsigned b=0; return a>b;}"
+assert 1 "main() {unsigned a=1; long b=-1; return a>b;}"
+assert 0 "main() {unsigned long a=1; int b=-1; return a>b;}"
+assert 1 "main() {char a=-128; unsigned char b=128; return a<b;}"
+assert 1 "main() {int a=-2147483647; unsigned b=2147483649; return a==b;}"
+assert 1 "main() {unsigned char a=200; unsigned char b=100; return a+b==300;}"
+assert 200 "main() {char a=100; char b=100; return a+b;}"
+assert 1 "main() {unsigned a=1; int b=-2; return a+b>0;}"
+assert 1 "main() {unsigned a=1; int b=-2; long x=a+b; return x>0;}"
+assert 1 "main() {int a=-1; unsigned b=2; return a/b==2147483647;}"
+assert 1 "main() {unsigned short a=1; short b=-2; return a+b<0;}"
+assert 1 "main() {unsigned a=3; long b=-4; return a+b<0;}"
+assert 1 "main() {char a=-1; unsigned short b=0; return a*b==0;}"
+assert 44 "main() {char c; return c=300;}"
+assert 1 "main() {long x=2147483648; return x>0;}"
+assert 8 "main() {return sizeof(sizeof(1));}"
+assert 1 "main() {int a=-1; return sizeof(a)<a;}"
+assert_error "int main() {int *p; p * 2; return 0;}"
+assert_error "int main() {int *p; p / 2; return 0;}"
+assert_error "int main() {int *p; int *q; p * q; return 0;}"
+assert_error "int main() {int *p; return 2 * p;}"
+assert_error "int main() {int a[2]; return a / 2;}"
+
+assert 1 "main() {struct {int a; int b;} x; x.a=1; x.b=2; return x.a;}"
+assert 2 "main() {struct {int a; int b;} x; x.a=1; x.b=2; return x.b;}"
+assert 3 "main() {struct {char a; int b; char c;} x; x.a=1; x.b=2; x.c=3; return x.c;}"
+assert 12 "main() {struct {char a; int b; char c;} x; return sizeof(x);}"
+assert 16 "main() {struct {char a; long b;} x; return sizeof(x);}"
+assert 4 "main() {struct {char a; short b; char c;} x; return &x.c-&x.a;}"
+assert 6 "main() {struct {char a; short b; char c;} x; return sizeof x;}"
+assert 8 "main() {struct {char a; long b;} x; return _Alignof(struct {char a; long b;});}"
+assert 2 "main() {struct {char a; short b;} x; return _Alignof(struct {char a; short b;});}"
+assert 24 "main() {struct {int a; struct {char b; long c;} d;} x; return sizeof(x);}"
+assert 7 "main() {struct {int a; struct {char b; long c;} d;} x; x.d.c=7; return x.d.c;}"
+assert 6 "main() {struct {int a[3];} x[2]; x[1].a[2]=6; return x[1].a[2];}"
+assert 48 "main() {struct {int a[3];} x[4]; return sizeof(x);}"
+assert 8 "main() {struct t {int a; int b;} x; struct t y; return sizeof(y);}"
+assert 8 "main() {struct t {int a; int b;}; return sizeof(struct t);}"
+assert 3 "main() {struct t {int a;} x; x.a=3; struct t *p=&x; return p->a;}"
+assert 5 "main() {struct t {int a;} x; struct t *p=&x; p->a=5; return x.a;}"
+assert 2 "main() {struct t {char a;} x; {struct t {long a;} y; return sizeof(y)-6;}}"
+assert 8 "main() {struct t *p; return sizeof(p);}"
+assert 3 "main() {struct n {int v; struct n *next;} a; struct n b; a.next=&b; b.v=3; return a.next->v;}"
+assert 7 "main() {struct t {int a; char b; long c;} x; struct t y; x.a=3; x.b=2; x.c=2; y=x; return y.a+y.b+y.c;}"
+assert 3 "main() {struct t {int a; int b;} x; struct t y; x.a=1; x.b=2; y=x; x.a=5; return y.a+y.b;}"
+assert 5 "struct p {int x; int y;}; main() {struct p a; a.x=2; a.y=3; return a.x+a.y;}"
+assert 16 "main() {struct p {int x; int y;} a[2]; return sizeof(a);}"
+assert_error "main() {struct t {int a;} x; return x.b;}"
+assert_error "main() {int x; return x.a;}"
+assert_error "main() {struct t x; return 0;}"
+assert_error "main() {struct t {int a;} x; struct u {int a;} y; x=y; return 0;}"
+assert_error "main() {{struct t {int a;} x;} struct t y; return 0;}"
+assert_error "int main() {struct {int a;} s; int x; x = s; return x;}"
+assert_error "int main() {struct {int a;} s; s = 1; return 0;}"
+assert_error "int main() {struct {int a;} s; long *p; p = s; return 0;}"
+assert_error "int main() {struct {int a;} s; return s;}"
+assert_error "struct S {int a;} g; int main() {return g;}"
+assert_error "int main() {struct {int a;} s; return s+1;}"
+assert_error "int main() {struct {int a;} s; int x=0; x+=s; return x;}"
+assert_error "int main() {struct {int a;} s; return s-1;}"
+assert_error "int main() {struct {int a;} s, t; return s == t;}"
+assert_error "int main() {struct {int a;} s; return s != 0;}"
+assert_error "int main() {struct {int a;} s; return s < 1;}"
+assert_error "int main() {struct {int a;} s; return 1 >= s;}"
+assert_error "int main() {struct {int a;} s; return s * 2;}"
+assert_error "int main() {struct {int a;} s; return 2 / s;}"
+assert_error "int main() {struct {int a;} s; return s % 2;}"
+assert_error "int main() {struct {int a;} s; int x=1; x *= s; return x;}"
+
+assert 8 "main() {union {int a; char b[6];} x; return sizeof(x);}"
+assert 4 "main() {union {int a; char b[3];} x; return sizeof(x);}"
+assert 4 "main() {union {int a; char b[3];} x; return _Alignof(union {int a; char b[3];});}"
+assert 3 "main() {union {int a; char b[4];} x; x.a=515; return x.b[0];}"
+assert 2 "main() {union {int a; char b[4];} x; x.a=515; return x.b[1];}"
+assert 7 "main() {union u {int a; long b;} x; union u *p=&x; p->b=7; return x.a;}"
+assert 16 "main() {struct {char k; union {int i; long l;};} x; return sizeof(x);}"
+assert 5 "main() {struct {char k; union {int i; long l;};} x; x.l=5; return x.i;}"
+assert 6 "main() {struct {int k; struct {int a; int b;}; int c;} x; x.b=6; return *(&x.k+2);}"
+assert 9 "main() {struct {int k; union {struct {char a; char b;}; short s;};} x; x.s=0; x.b=9; return x.s/256;}"
+assert 4 "int main() { struct { int a; union { int b; char c; }; } s; s.a = 1; s.b = 0; s.c = 3; return s.a + s.b; }"
+assert_error "main() {struct t {int a;} x; union t y; return 0;}"
+assert_error "int main() { struct { int a; union { int a; }; } s; return 0; }"
+assert_error "int main() { struct { union { int b; }; int b; } s; return 0; }"
+assert_error "int main() { struct { struct { int x; }; union { struct { int x; }; }; } s; return 0; }"
+
+assert 0 "main() {enum {zero, one, two}; return zero;}"
+assert 1 "main() {enum {zero, one, two}; return one;}"
+assert 2 "main() {enum {zero, one, two,}; return two;}"
+assert 5 "main() {enum {five=5, six, seven}; return five;}"
+assert 7 "main() {enum {five=5, six, seven}; return seven;}"
+assert 12 "main() {enum {a=3, b=a*4}; return b;}"
+assert 9 "main() {enum {a=-1, b, c=b+9}; return c;}"
+assert 4 "main() {enum t {x, y} e; return sizeof(e);}"
+assert 4 "main() {enum t {x, y}; enum t e; return sizeof(e);}"
+assert 1 "main() {enum t {x, y}; enum t e=y; return e;}"
+assert 3 "main() {enum {a=1}; {enum {a=3}; return a;}}"
+assert 1 "main() {enum {a=1}; {enum {a=3};} return a;}"
+assert 40 "main() {enum {n=10}; int x[n]; return sizeof(x);}"
+assert 24 "main() {int x[2*3]; return sizeof(x);}"
+assert 12 "main() {enum {n=3}; char m[n][n+1]; return sizeof(m);}"
+assert 3 "main() {enum {n=3}; struct {int a[n];} s; s.a[2]=3; return s.a[n-1];}"
+assert 1 "main() {enum {a=4==4, b=2<1}; return a+b;}"
+assert 15 "int main() {enum {X = ~(unsigned)0 >> 28}; return X;}"
+assert 1 "int main() {enum {X = ((unsigned)0 - 1) / 2}; return X == 2147483647;}"
+assert 1 "int main() {enum {X = (unsigned char)255 + 1 == 256}; return X;}"
+assert 1 "int main() {enum {X = 2147483647 + 1 < 0}; return X;}"
+assert 254 "int main() {enum {X = (unsigned char)(254 * 257)}; return X;}"
+assert 1 "enum { A = 2147483647, B = -2147483647 - 1 }; int main() { return A + B == -1; }"
+assert_error "main() {int n=3; int x[n]; return 0;}"
+assert_error "main() {enum {a, a}; return 0;}"
+assert_error "main() {int a; enum {a}; return 0;}"
+assert_error "main() {enum t x; return 0;}"
+assert_error "main() {enum {a=1/0}; return 0;}"
+assert_error "int main() {enum {X = ~((long)1 << 63), Y}; return Y;}"
+assert_error "enum { A = 2147483647, B }; int main() { return 0; }"
+assert_error "enum { A = 4294967295 }; int main() { return A == -1; }"
+assert_error "enum { A = (long)-2147483647 - 2 }; int main() { return 0; }"
+
+assert 1 "main() {typedef int t; t x=1; return x;}"
+assert 1 "main() {typedef struct {int a;} t; t x; x.a=1; return x.a;}"
+assert 1 "main() {typedef int t; {t t=1; return t;}}"
+assert 2 "main() {typedef struct {int a;} t; {typedef int t;} t x; x.a=2; return x.a;}"
+assert 4 "main() {typedef t; t x; return sizeof(x);}"
+assert 3 "main() {typedef int *p; int a=3; p x=&a; return *x;}"
+assert 8 "main() {typedef long t; return sizeof(t);}"
+assert 12 "main() {typedef int a3[3]; a3 x; return sizeof(x);}"
+assert 24 "main() {typedef int a3[3]; a3 x[2]; return sizeof x;}"
+assert 5 "main() {typedef int a3[3]; a3 x; x[2]=5; return x[2];}"
+assert 2 "main() {typedef int t, *pt; t a=2; pt b=&a; return *b;}"
+assert 6 "main() {typedef int t; int a=2; int b=3; {int t=a*b; return t;}}"
+assert 6 "main() {typedef int t; t *x; int a=6; x=&a; return *x;}"
+assert 6 "main() {typedef int t; int x=2; int y=3; {int t=3; x = t * x;} return x;}"
+assert 3 "main() {typedef struct n node; struct n {int v; node *next;}; node a; node b; a.next=&b; b.v=3; return a.next->v;}"
+assert 4 "typedef int myint; main() {myint x; return sizeof(x);}"
+assert 9 "typedef struct {int x; int y;} point; main() {point p; p.x=4; p.y=5; return p.x+p.y;}"
+assert 1 "main() {typedef int t; long t2; return sizeof(t)<sizeof(t2);}"
+assert_error "main() {typedef int t; return t;}"
+assert_error "main() {typedef int t; long t x; return 0;}"
+assert_error "main() {typedef int t; int t; return 0;}"
+
+assert 3 "f(x,y) {return x-y;} main() {int (*fp)(int,int); fp=f; return fp(5,2);}"
+assert 3 "f(x,y) {return x-y;} main() {int (*fp)(int,int)=&f; return (*fp)(5,2);}"
+assert 7 "f(x,y) {return x+y;} main() {typedef int (*fn)(int,int); fn fp=f; return fp(3,4);}"
+assert 4 "f(x) {return x*2;} main() {int (*fp[2])(int); fp[1]=f; return fp[1](2);}"
+assert 3 "f(x) {return x+1;} main() {return (*f)(2);}"
+assert 8 "main() {return sizeof(int (*)(int));}"
+assert 40 "main() {int *(*a[5])(void); return sizeof(a);}"
+assert 12 "main() {int (*p)[3]; return sizeof(*p);}"
+assert 7 "main() {int a[2][3]; a[1][2]=7; int (*p)[3]=a; return (*(p+1))[2];}"
+assert 5 "main() {int (x)=5; return x;}"
+assert 1 "main() {void *p; return sizeof(*p);}"
+assert_error "main() {int x=1; return x(2);}"
+assert_error "main() {void x; return 0;}"
+assert_error "main() {int short void x; return 0;}"
+
+assert 7 "int add(int x, int y); int main() {return add(3,4);}"
+assert 3 "int f(int a, char *b) {return a+*b;} int main() {char c=2; return f(1,&c);}"
+assert 3 "void g(void) {return;} int main() {g(); return 3;}"
+assert 8 "static int s(int x) {return x*2;} int main() {return s(4);}"
+assert 8 "static int s(int x); int s(int x) {return x*2;} int main() {return s(4);}"
+assert 1 "char f(int x) {return x;} int main() {return f(257);}"
+assert 5 "int main() {return f(5);} int f(int x) {return x;}"
+assert 6 "long f(long); int main() {return f(2);} long f(long x) {return x*3;}"
+assert 4 "int main() {int sub(int, int); return sub(7,3);}"
+assert 1 "char c(void); int main() {return sizeof(c());}"
+assert 3 "int f(); int f(int x) {return x;} int main() {return f(3);}"
+assert 5 "int f(int a[3]) {return a[1];} int main() {int a[2]; a[1]=5; return f(a);}"
+assert 7 "struct S {int a; int b;}; int f(struct S *s) {return s->b;} int main() {struct S s; s.a=3; s.b=7; return f(&s);}"
+assert 7 "struct S {int a; int b;}; struct S g; struct S *f(void) {g.b=7; return &g;} int main() {return f()->b;}"
+assert 3 "static int k(void); int k(void) { return 3; } int main() { return k(); }"
+assert 21 "f(a,b,c,d,e,f) { return a+b+c+d+e+f; } int main() { return f(1,2,3,4,5,6); }"
+assert_error "int f(int x); int main() {return f(1,2);}"
+assert_error "int f(int *p); int main() {return f(1);}"
+assert_error "int f(int x); int main() {int a; return f(&a);}"
+assert_error "void g(void) {return 1;} int main() {return 0;}"
+assert_error "int f(int); char f(int); int main() {return 0;}"
+assert_error "int f(void) {return 0;} int f(void) {return 1;} int main() {return 0;}"
+assert_error "int f(int) {return 0;} int main() {return 0;}"
+assert_error "struct S {int a; int b;}; int f(struct S s) {return s.b;} int main() {return 0;}"
+assert_error "struct S {int a;}; struct S f(void); int main() {return 0;}"
+assert_error "struct S {int a;}; int main() {int (*p)(struct S); return 0;}"
+assert_error "void g(void) {} int main() { int x = g(); return x; }"
+assert_error "void g(void) {} int main() { return g(); }"
+assert_error "void g(void) {} int main() { int x; x = g(); return x; }"
+assert_error "int k(void) { return 0; } static int k(void); int main() { return k(); }"
+assert_error "f(a,b,c,d,e,f,g) { return a; } int main() { return 0; }"
+
+assert 3 "int x; int main() {x=3; return x;}"
+assert 5 "int x = 5; int main() {return x;}"
+assert 7 "int x; int x; int x = 7; int x; int main() {return x;}"
+assert 6 "int a[4]; int main() {a[2]=6; return a[2]+a[0];}"
+assert 44 "char c = 300; int main() {return c;}"
+assert 1 "long l = -1; int main() {return l+2;}"
+assert 4 "struct P {int x; int y;} p; int main() {p.y=4; return p.x+p.y;}"
+assert 2 "int x; int main() {int x=2; return x;}"
+assert 3 "int main() {extern int g; g=3; return g;} int g;"
+assert 4 "static int s = 4; int main() {return s;}"
+assert 9 "int *p; int x; int main() {p=&x; *p=9; return x;}"
+assert 4 "extern int e; int main() {return sizeof(e);}"
+assert 3 "int x=1; int f(void) {x=x+1; return x;} int main() {f(); return f();}"
+assert 5 "int x, *p; int main() {p=&x; x=5; return *p;}"
+assert 1 "void *p = &p; int main() { return p == &p; }"
+assert 3 "int a[]; int main() { a[0] = 3; return a[0]; }"
+assert 16 "int b[]; int b[4]; int main() { return sizeof(b); }"
+assert_error "int x=1; int x=2; int main() {return 0;}"
+assert_error "int x; char x; int main() {return 0;}"
+assert_error "static extern int x; int main() {return 0;}"
+assert_error "int main() {return x;} int x;"
+assert_error "int x; int x(void); int main() {return 0;}"
+assert_error "int main() { int a[]; return 0; }"
+assert_error "int a[]; int main() { return sizeof(a); }"
+
+assert 6 "int main() {int a[3]={1,2,3}; return a[0]+a[1]+a[2];}"
+assert 0 "int main() {int a[3]={1}; return a[1]+a[2];}"
+assert 12 "int main() {int a[]={1,2,3}; return sizeof(a);}"
+assert 3 "int main() {int a[]={1,2,3,}; return a[2];}"
+assert 6 "int main() {int a[2][3]={{1,2,3},{4,5,6}}; return a[1][2];}"
+assert 5 "int main() {int a[2][3]={1,2,3,4,5,6}; return a[1][1];}"
+assert 0 "int main() {int a[2][3]={{1},{4}}; return a[0][1]+a[1][2];}"
+assert 7 "int main() {int a[10]={[5]=7}; return a[5]+a[4]+a[6];}"
+assert 24 "int main() {int a[]={[5]=7}; return sizeof(a);}"
+assert 9 "int main() {int a[4]={1,[2]=5,4}; return a[0]+a[2]+a[3]-a[1]-1;}"
+assert 3 "int main() {struct {int x; int y;} p={1,2}; return p.x+p.y;}"
+assert 2 "int main() {struct {int x; int y;} p={.y=2}; return p.x+p.y;}"
+assert 12 "int main() {struct {int x; int y;} p={.y=2, .x=1}; return p.x*10+p.y;}"
+assert 4 "int main() {struct {int x; int a[2];} p={1,{2,3}}; return p.x+p.a[1];}"
+assert 4 "int main() {struct {int x; int a[2];} p={1,2,3}; return p.x+p.a[1];}"
+assert 6 "int main() {struct P {int x; int y;} ps[2]={{1,2},{3}}; return ps[0].x+ps[0].y+ps[1].x+ps[1].y;}"
+assert 7 "int main() {struct P {int x; int y;} ps[]={[1].y=7}; return ps[1].y+ps[0].x;}"
+assert 3 "int main() {struct P {int x; int y;} p={1,2}; struct P q=p; return q.x+q.y;}"
+assert 4 "int main() {union {int a; char b;} u={.b=4}; return u.b;}"
+assert 3 "int main() {int x={3}; return x;}"
+assert 1 "int main() {char c[3]={257,1}; return c[0];}"
+assert 3 "int main() {int i=0; while (i<3) {int a[2]={i}; if (a[1]) return 9; i=i+1;} return i;}"
+assert 6 "int a[3]={1,2,3}; int main() {return a[0]+a[1]+a[2];}"
+assert 24 "int a[]={1,2,3,4,5,6}; int main() {return sizeof(a);}"
+assert 7 "int a[10]={[5]=7}; int main() {return a[5]+a[9];}"
+assert 5 "struct {char c; long l;} s={1,4}; int main() {return s.c+s.l;}"
+assert 8 "struct {int x; int y;} s={.y=8}; int main() {return s.x+s.y;}"
+assert 4 "union {int a; char b[4];} u={.b={1,2,3,4}}; int main() {return u.b[3];}"
+assert 5 "int x=5; int *p=&x; int main() {return *p;}"
+assert 3 "int a[3]={1,2,3}; int *p=a+2; int main() {return *p;}"
+assert 2 "int a[3]={1,2,3}; int *p=&a[1]; int main() {return *p;}"
+assert 3 "int f(void) {return 3;} int (*fp)(void)=f; int main() {return fp();}"
+assert 6 "int a[2][3]={{1,2,3},{4,5,6}}; int main() {return a[1][2];}"
+assert 5 "struct S {int a; union {int b; char c;};}; int main() { struct S s = {.b = 5}; return s.b; }"
+assert 8 "struct S {int a; struct {int b; int c;};}; struct S g = {1, .c = 7}; int main() { return g.a + g.c + g.b; }"
+assert_error "int main() {int a[2]={1,2,3}; return 0;}"
+assert_error "int main() {struct {int x;} p={.y=1}; return 0;}"
+assert_error "int main() {int a[2]={[2]=1}; return 0;}"
+assert_error "int main() {int a[]; return 0;}"
+assert_error "int main() {int a[3]=1; return 0;}"
+assert_error "int x; int y=x; int main() {return 0;}"
+assert_error "int x; char c=&x; int main() {return 0;}"
+assert_error "int main() {int a[][]={{1}}; return 0;}"
+assert_error "struct S {int a; union {int b;};}; int main() { struct S s = {.d = 5}; return 0; }"
+
+assert 32 "int main() {struct {char a; _Alignas(16) char b;} x; return sizeof(x);}"
+assert 16 "int main() {struct {char a; _Alignas(16) char b;} x; return _Alignof(struct {char a; _Alignas(16) char b;});}"
+assert 24 "int main() {struct {char a; _Alignas(long) int b[3];} x; return sizeof(x);}"
+assert 15 "int main() {char a; _Alignas(16) char b; return &a-&b;}"
+assert 7 "int main() {char a; _Alignas(8) char b; return &a-&b;}"
+assert 7 "_Alignas(16) char g; int main() {g=7; return g;}"
+assert 8 "int main() {long x; return sizeof x;}"
+assert 4 "int main() {int x; return sizeof(x+1);}"
+assert 8 "int main() {int *p; return sizeof(p+1);}"
+assert 1 "int main() {int x=1; sizeof(x=5); return x;}"
+assert 1 "int main() {return sizeof(char);}"
+assert 8 "int main() {return _Alignof(long);}"
+assert 4 "int main() {return _Alignof(int[3]);}"
+assert 14 "int main() { _Alignas(32) int x; _Alignas(64) char y[3]; long a = (long)&x; long b = (long)y; x = 5; y[2] = 7; return (a % 32 == 0) + (b % 64 == 0) + x + y[2]; }"
+assert 3 "int main() { _Alignas(32) int x[4] = {1, 2}; return ((long)x & 31) + x[0] + x[1] + x[3]; }"
+assert_error "int main() {_Alignas(3) int x; return 0;}"
+assert_error "int main() {_Alignas(2) int x; return 0;}"
+assert_error "int main() {return sizeof(_Alignas(8) int);}"
+assert_error "int main() {struct S; return sizeof(struct S);}"
+assert_error "extern int a[]; int main() {return sizeof(a);}"
+assert_error "struct T; int main() { return _Alignof(struct T); }"
+assert_error "struct T; int main() { _Alignas(struct T) int x; return 0; }"
+
+assert 1 "int main() {return (char)257;}"
+assert 255 "int main() {return (unsigned char)-1;}"
+assert 1 "int main() {return (short)65537;}"
+assert 1 "int main() {return (int)(char)-1 + 2;}"
+assert 1 "int main() {return (long)-1 < 0;}"
+assert 1 "int main() {return (unsigned)-1 > 0;}"
+assert 1 "int main() {return (_Bool)2;}"
+assert 0 "int main() {return (_Bool)0;}"
+assert 1 "int main() {_Bool b=256; return b;}"
+assert 2 "int main() {_Bool b; b=-1; return b+1;}"
+assert 1 "int main() {return sizeof(_Bool);}"
+assert 1 "_Bool g=5; int main() {return g;}"
+assert 2 "typedef int T; int main() {long x=258; return (T)(char)x;}"
+assert 4 "int main() {int T=3; return (T)+1;}"
+assert 4 "typedef int T; int main() {int T=3; return (T)+1;}"
+assert 3 "int main() {int x=3; long a=(long)&x; int *p=(int*)a; return *p;}"
+assert 3 "int main() {int x=258; char *p=(char*)&x; return p[0]+p[1];}"
+assert 5 "void *malloc(long n); int main() {int *p=(int*)malloc(8); p[1]=5; return p[1];}"
+assert 3 "int main() {(void)5; return 3;}"
+assert 0 "int main() {_Alignas(16) char a; char b; return (long)&a - (long)&a/16*16;}"
+assert 8 "int main() {struct {char a; _Alignas(long) int b;} x; return (char*)&x.b - (char*)&x;}"
+assert 2 "char c=(char)258; int main() {return c;}"
+assert_error "int main() {int x=3; (int)x=4; return x;}"
+assert_error "int main() {struct {int a;} p; return (int)p;}"
+assert_error "int main() {int x; return (_Bool int)x;}"
+
+assert 1 "int main() {return 2 && 3;}"
+assert 0 "int main() {return 2 && 0;}"
+assert 0 "int main() {return 0 || 0;}"
+assert 1 "int main() {return 0 || 5;}"
+assert 1 "int main() {return !0;}"
+assert 0 "int main() {return !5;}"
+assert 1 "int main() {return !!7;}"
+assert 1 "int main() {return 1 || 0 && 0;}"
+assert 1 "int main() {return 0 && 1 || 1;}"
+assert 0 "int main() {int x=0; 0 && (x=1); return x;}"
+assert 0 "int main() {int x=0; 1 || (x=1); return x;}"
+assert 2 "int main() {int x=0; 1 && (x=2); return x;}"
+assert 2 "int main() {int x=0; 0 || (x=2); return x;}"
+assert 1 "int main() {int *p=0; return !p;}"
+assert 1 "int main() {int x; int *p=&x; return p && 1;}"
+assert 8 "int main() {int a[!0 + 1]; return sizeof(a);}"
+assert 1 "int main() {enum {A = 1 && 2}; return A;}"
+assert 3 "int main() {int a=1; int b=0; if (a && !b) return 3; return 4;}"
+assert 2 "int main() {int a[2]; int *p=a; if (p) return 2; return 3;}"
+assert 2 "int main() {int a[2]; if (a) return 2; return 3;}"
+assert_error "int main() {struct {int a;} s; return !s;}"
+assert_error "int main() {struct {int a;} s; return s && 1;}"
+assert_error "int main() {struct {int a;} s; if (s) return 1; return 0;}"
+assert_error "int main() {struct {int a;} s; while (s) return 1; return 0;}"
+assert_error "int main() {struct {int a;} s; do return 1; while (s); return 0;}"
+
+assert 1 "int main() {return 10%3;}"
+assert 255 "int main() {return -7%3;}"
+assert 0 "int main() {return (unsigned)-7%3;}"
+assert 4 "int main() {long a=-5; unsigned long b=a; return b%7;}"
+assert 2 "int main() {return 6&3;}"
+assert 7 "int main() {return 6|3;}"
+assert 5 "int main() {return 6^3;}"
+assert 255 "int main() {return ~0;}"
+assert 1 "int main() {return ~5 + 7;}"
+assert 16 "int main() {return 1<<4;}"
+assert 16 "int main() {return 256>>4;}"
+assert 252 "int main() {return -16>>2;}"
+assert 15 "int main() {return (unsigned)-16>>28;}"
+assert 255 "int main() {char c=-1; return c>>1;}"
+assert 255 "int main() {return 1<<31>>31;}"
+assert 1 "int main() {return (unsigned)1<<31>>31;}"
+assert 4 "int main() {long x=1; return (x<<40)>>38;}"
+assert 3 "int main() {return 1|2^3&4;}"
+assert 8 "int main() {return 1<<2+1;}"
+assert 1 "int main() {return 5&3==3;}"
+assert 0 "int main() {unsigned char c=255; return ~c+256;}"
+assert 9 "int main() {enum {A=1<<3|1}; return A;}"
+assert 3 "int x = 7%4; int main() {return x;}"
+assert 1 "int main() {return 1 && 2 | 0;}"
+assert_error "int main() {int *p; return p%2;}"
+assert_error "int main() {int *p; return ~p;}"
+assert_error "int main() {int *p; return p<<1;}"
+
+assert 7 "int main() {int i=2; i+=5; return i;}"
+assert 7 "int main() {int i=2; return i+=5;}"
+assert 3 "int main() {int i=5; i-=2; return i;}"
+assert 6 "int main() {int i=3; i*=2; return i;}"
+assert 3 "int main() {int i=7; i/=2; return i;}"
+assert 1 "int main() {int i=7; i%=3; return i;}"
+assert 2 "int main() {int i=6; i&=3; return i;}"
+assert 7 "int main() {int i=6; i|=3; return i;}"
+assert 5 "int main() {int i=6; i^=3; return i;}"
+assert 12 "int main() {int i=3; i<<=2; return i;}"
+assert 3 "int main() {int i=13; i>>=2; return i;}"
+assert 1 "int main() {char c=255; c+=2; return c;}"
+assert 6 "int main() {int i=1; int j=2; i+=j+=3; return i;}"
+assert 3 "int main() {int a[3]; a[0]=1; a[1]=2; a[2]=3; int *p=a; p+=2; return *p;}"
+assert 2 "int main() {int a[3]; a[0]=1; a[1]=2; a[2]=3; int *p=a+2; p-=1; return *p;}"
+assert 3 "int main() {int i=2; ++i; return i;}"
+assert 1 "int main() {int i=2; --i; return i;}"
+assert 3 "int main() {int i=2; return ++i;}"
+assert 2 "int main() {int i=2; return i++;}"
+assert 3 "int main() {int i=2; i++; return i;}"
+assert 2 "int main() {int i=2; return i--;}"
+assert 1 "int main() {int i=2; i--; return i;}"
+assert 127 "int main() {char c=127; return c++;}"
+assert 128 "int main() {unsigned char c=127; c++; return c;}"
+assert 2 "int main() {int a[3]; a[0]=1; a[1]=2; int *p=a; p++; return *p;}"
+assert 1 "int main() {int a[3]; a[0]=1; a[1]=2; int *p=a; return *p++;}"
+assert 2 "int main() {int a[3]; a[0]=1; a[1]=2; int *p=a; return *++p;}"
+assert 2 "int main() {int a[3]; a[0]=1; int *p=a; (*p)++; return a[0];}"
+assert 5 "int main() {struct {int x; long y;} s; s.x=2; s.y=1; s.x+=3; return s.x;}"
+assert 1 "int n; int f(void) {n++; return 0;} int main() {int a[2]; a[0]=1; a[f()]+=1; return n;}"
+assert 2 "int n; int f(void) {n++; return 0;} int main() {int a[2]; a[0]=1; a[f()]++; return a[0];}"
+assert 1 "int main() {_Bool b=1; int r=b++; return r;}"
+assert 0 "int main() {_Bool b=0; int r=b--; return r;}"
+assert 1 "int main() {_Bool b=0; b++; return b;}"
+assert 1 "int main() {_Bool b=0; b--; return b;}"
+assert 3 "int main() {int n=0; int a[2]; a[0]=5; int *p=a; p++; p--; n+=*p==5; n+=a[0]++==5; n+=a[0]==6; return n;}"
+assert_error "int main() {int a[2]; a+=1; return 0;}"
+assert_error "int main() {int *p; p*=2; return 0;}"
+assert_error "int main() {int *p; p<<=1; return 0;}"
+assert_error "int main() {1++; return 0;}"
+assert_error "int main() {int x=0; x++ = 3; return x;}"
+assert_error "int main() {int a[2]; a++; return 0;}"
+
+assert 2 "int main() {return 1 ? 2 : 3;}"
+assert 3 "int main() {return 0 ? 2 : 3;}"
+assert 5 "int main() {int x=0; return x ? 4 : x+1 ? 5 : 6;}"
+assert 1 "int main() {int x=0; return 1 ? x=1, x : 2;}"
+assert 8 "int main() {return sizeof(1 ? 1 : (long)2);}"
+assert 255 "int main() {return 0 ? 1 : (unsigned char)255;}"
+assert 2 "int main() {int a[2]; a[1]=2; int *p=0; return *(p ? p : a+1);}"
+assert 2 "int main() {int a[2]; a[1]=2; int *p=a+1; return *(1 ? p : 0);}"
+assert 4 "int main() {int x=3; int y=4; int *p=0 ? &x : &y; return *p;}"
+assert 8 "int main() {char c; void *p=&c; return sizeof(1 ? p : &c);}"
+assert 6 "int main() {struct {int a; int b;} s, t; s.a=6; t.a=7; return (1 ? s : t).a;}"
+assert 3 "int n; void f(void) {n=3;} int main() {1 ? f() : f(); return n;}"
+assert 2 "int n; int f(int x) {n++; return x;} int main() {1 ? f(1) : f(2); return n+1;}"
+assert 7 "int main() {int a[7 ? 7 : 1]; return sizeof(a)/sizeof(int);}"
+assert 4 "int x = 0 ? 1 : 4; int main() {return x;}"
+assert 3 "int main() {return (1, 2, 3);}"
+assert 5 "int main() {int i=2, j=3; return (i=5, j=i);}"
+assert 2 "int main() {int x=1; x=(x+=1, x); return x;}"
+assert 6 "int main() {int i; int j; int s=0; for(i=0, j=3; i<j; i++, j--) s+=j-i+1; return s;}"
+assert_error "int main() {int *p; return 1 ? p : 1;}"
+assert_error "int main() {int *p; char *q; return *(1 ? p : q);}"
+assert_error "int main() {struct {int a;} s; return 1 ? s : 1;}"
+assert_error "int main() {int x; (1, x) = 2; return x;}"
+assert_error "int x = (1, 2); int main() {return x;}"
+
+assert 3 "int main() {int i=0; while(1) {if(i==3) break; i++;} return i;}"
+assert 4 "int main() {int i; for(i=0; i<10; i++) {if(i==4) break;} return i;}"
+assert 10 "int main() {int i=0; int j=0; while(i<10) {i++; if(i>5) continue; j+=2;} return j;}"
+assert 10 "int main() {int i; int j=0; for(i=0; i<10; i++) {if(i%2) continue; j+=2;} return j;}"
+assert 10 "int main() {int i; for(i=0; i<10; i++) continue; return i;}"
+assert 6 "int main() {int i; int j; int n=0; for(i=0; i<3; i++) {for(j=0; j<10; j++) {if(j==2) break; n++;}} return n;}"
+assert 3 "int main() {int i; int n=0; for(i=0; i<3; i++) {int j=0; while(1) {j++; if(j<2) continue; break;} n++;} return n;}"
+assert_error "int main() {break; return 0;}"
+assert_error "int main() {continue; return 0;}"
+assert_error "int main() {if(1) break; return 0;}"
+
+assert 5 "int main() {int i=0; do i++; while(i<5); return i;}"
+assert 1 "int main() {int i=0; do {i++;} while(0); return i;}"
+assert 1 "int main() {int i=10; do i-=9; while(i>5); return i;}"
+assert 3 "int main() {int i=0; do {i++; if(i==3) break;} while(1); return i;}"
+assert 4 "int main() {int i=0; int j=0; do {i++; if(i%2) continue; j+=2;} while(i<5); return j;}"
+assert 7 "int main() {int i=0; do {i++; continue;} while(i<7); return i;}"
+assert_error "int main() {int i=0; do i++; return i;}"
+
+assert 5 "int main() {int i=0; switch(0) {case 0: i=5; break; case 1: i=6; break; case 2: i=7; break;} return i;}"
+assert 6 "int main() {int i=0; switch(1) {case 0: i=5; break; case 1: i=6; break; case 2: i=7; break;} return i;}"
+assert 7 "int main() {int i=0; switch(2) {case 0: i=5; break; case 1: i=6; break; case 2: i=7; break;} return i;}"
+assert 0 "int main() {int i=0; switch(3) {case 0: i=5; break; case 1: i=6; break; case 2: i=7; break;} return i;}"
+assert 5 "int main() {int i=0; switch(0) {case 0: i=5; break; default: i=7;} return i;}"
+assert 7 "int main() {int i=0; switch(1) {case 0: i=5; break; default: i=7;} return i;}"
+assert 2 "int main() {int i=0; switch(1) {default: i=2; break; case 0: i=5;} return i;}"
+assert 6 "int main() {int i=0; switch(1) {case 1: i++; case 2: i++; case 3: i+=4;} return i;}"
+assert 3 "int main() {int i=0; switch(0) {case 1: i=5;} return i+3;}"
+assert 11 "int main() {enum {A, B, C}; int i=0; switch(B) {case A: i=10; break; case B: i=11; break; case C: i=12;} return i;}"
+assert 2 "int main() {int i=0; switch(-1) {case -1: i=2; break; case 1: i=3;} return i;}"
+assert 4 "int main() {long x=1; x<<=32; switch(x) {case 0: return 3; case (long)1<<32: return 4;} return 5;}"
+assert 1 "int main() {char c=255; switch(c) {case -1: return 1; case 255: return 2;} return 3;}"
+assert 9 "int main() {int i; int n=0; for(i=0; i<4; i++) {switch(i) {case 1: continue; case 2: break; default: n+=i;} n+=2;} return n;}"
+assert 3 "int main() {int i=0; switch(1) {case 1: switch(2) {case 2: i=1; break;} i+=2; break; case 2: i=9;} return i;}"
+assert 4 "int f(int x) {switch(x) {case 1: return 2; case 2: return 4;} return 6;} int main() {return f(2);}"
+assert_error "int main() {case 1: return 0;}"
+assert_error "int main() {default: return 0;}"
+assert_error "int main() {switch(1) {case 1: case 1: return 0;} return 1;}"
+assert_error "int main() {switch(1) {default: default: return 0;} return 1;}"
+assert_error "int main() {int x=1; switch(1) {case x: return 0;} return 1;}"
+assert_error "int main() {int *p; switch(p) {case 0: return 0;} return 1;}"
+
+assert 3 "int main() {int i=0; goto a; a: i++; b: i++; c: i++; return i;}"
+assert 2 "int main() {int i=0; goto e; d: i++; e: i++; f: i++; return i;}"
+assert 1 "int main() {int i=0; goto i; g: i++; h: i++; i: i++; return i;}"
+assert 5 "int main() {int i=0; loop: i++; if(i<5) goto loop; return i;}"
+assert 4 "int main() {int i=0; {{goto out;} i=9;} out: i+=4; return i;}"
+assert 2 "int f(void) {goto x; return 1; x: return 2;} int main() {goto x; return 1; x: return f();}"
+assert 3 "int main() {int x=3; x: return x;}"
+assert 3 "int main() {int i=3; goto out; i=4; out: ; return i;}"
+assert 2 "int main() {int i=0; a: ; i++; if(i<2) goto a; return i;}"
+assert 1 "int main() {;;; return 1;}"
+assert_error "int main() {goto a; return 0;}"
+assert_error "int main() {a: a: return 0;}"
+assert_error "int f(void) {a: return 0;} int main() {goto a; return 1;}"
+assert_error "int main() {goto 1; return 0;}"
+assert_error "int main() {return;}"
+assert_error "int main() {int x = ; return 0;}"
+assert_error "int main() {a: }"
+
+assert 10 "int main() {int s=0; for(int i=0; i<5; i++) s+=i; return s;}"
+assert 3 "int main() {int i=3; for(int i=0; i<5; i++) {} return i;}"
+assert 6 "int main() {int s=0; for(int i=0, j=3; i<j; i++, j--) s+=j-i+1; return s;}"
+assert 5 "int main() {int i=0; for(;;) {if(i==5) break; i++;} return i;}"
+assert 4 "int main() {int i=0; for(; i<4;) i++; return i;}"
+assert 8 "int main() {int s=0; for(int i=0; i<2; i++) for(int j=0; j<4; j++) s++; return s;}"
+assert 2 "int main() {for(int i=0; i<3; i++) {int i=2; return i;} return 0;}"
+assert 5 "int main() {int i; for(i=0; i<5; i++) ; return i;}"
+assert_error "int main() {for(int i=0; i<3; i++) {} return i;}"
+assert_error "int main() {int i; for(i=0; i<3; i++;) {} return i;}"
+
 echo OK